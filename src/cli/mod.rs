@@ -0,0 +1,1118 @@
+//! non-interactive subcommands (`timetracker <command> [args]`);
+//! the numbered main menu in main.rs stays the default without arguments
+
+use std::error;
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use rusqlite::Connection;
+
+use crate::db;
+
+const USAGE: &str = "\
+Usage: timetracker [command] [arguments]
+
+Without a command the interactive main menu is started.
+
+Commands:
+  start <activity> [--tags <tags>] [--note <text>]
+                                start a timer for an activity (id or name);
+                                tags (comma separated) go on all its sessions
+  stop [--at <time> | --discard] [--note <text>]
+                                stop the running timer and enter its time;
+                                `--at` ends it at an earlier time (HH:MM or
+                                YYYY-MM-DD HH:MM), `--discard` drops it;
+                                `--note` notes what was done
+  status                        show the running timer (if any)
+  add <activity> <duration> [date] [--tags <tags>] [--note <text>]
+                                enter a time manually; duration as HH:MM or
+                                decimal hours, date as YYYY-MM-DD, `today`
+                                (default) or `yesterday`
+  delete <activity> <date>      delete the entry of an activity on a date
+  sessions [from] [to]          list tracked intervals (default today)
+  sessions delete <id>          delete a single tracked interval
+  sessions note <id> [text]     set (or w/o text remove) the note of one
+  sessions tag <id> <tags>      add tags to a tracked interval
+  sessions untag <id> <tag>
+  sessions split <id> <time>    split a tracked interval in two at a time
+                                (HH:MM or YYYY-MM-DD HH:MM)
+  sessions reassign <id> <activity>
+                                move a tracked interval to another activity
+  history [from] [to] [activity]
+                                list entries (hours per day and activity) of
+                                any date range (default the last 7 days)
+  edit set <activity> <date> <duration>
+                                change the hours of an entry
+  edit move <activity> <into> <date> <duration>
+                                move hours of an entry to another activity
+  stats [--detailed | --year] [--tag <tag>]
+                                print statistics; `--tag` restricts them to
+                                time tagged w/ a tag
+  stats --from <date> [--to <date>]
+                                report of a date range (`--to` defaults to
+                                today): total, average per day and share of
+                                every activity
+  stats <--weeks | --months> [year]
+                                totals per ISO week or month, per activity
+                                and overall, of a year (default all time)
+  stats --weekdays              total and average hours per weekday, per
+                                activity and overall
+  stats --breaks [from] [to]    work and break time of tracked intervals
+                                per activity, week and day (default the
+                                last 4 weeks): break ratio, number and
+                                average length of breaks
+  stats --hours [year]          hours per hour of the day (from tracked
+                                intervals), per activity and overall, of a
+                                year (default all time)
+  stats --heatmap [year]        calendar heatmap of daily totals of an ISO
+                                week year (default this one)
+  stats --year --activity <activities>
+                                yearly statistics of some activities only
+                                (comma separated), their hours summed up
+  tags                          list tags w/ their totals
+  thresholds                    show the hour thresholds of yearly stats
+                                and streaks
+  thresholds <days | weeks> <hours | default>
+                                set them (e.g. `thresholds days 2,4,8`)
+  thresholds <streak | streak-activity> <hours | default>
+                                hours a day needs to count towards a streak
+                                (in total / on a single activity)
+  goals                         list hour goals w/ their progress
+  goals set <activity | all> <day | week | month> <duration>
+                                set an hour goal of an activity or overall
+                                (`all`), e.g. `goals set all week 40`
+  goals remove <activity | all> <day | week | month>
+  export csv history <file> [--from <date>] [--to <date>]
+                                [--delimiter <char>]
+                                write entries (date, ISO week, activity,
+                                hours, notes) as CSV; `-` as file writes to
+                                stdout, delimiter defaults to `,` (`tab` for
+                                tabs)
+  export csv activities <file> [--delimiter <char>]
+                                write all activities as CSV
+  export json <file>            write all data (activities, sessions, notes,
+                                tags, projects, goals, settings) as JSON
+                                document (to be imported elsewhere)
+  export ics <file> [--from <date>] [--to <date>]
+                                write work sessions as iCalendar events
+                                (summary the activity, description the note)
+  import json <file> [--duplicates <sum | skip>]
+                                import a JSON document into an empty db or
+                                merge it into this one; activities match by
+                                name, sessions on days an activity already
+                                has hours on are skipped (default) or summed
+  import timewarrior <path>     import Timewarrior intervals (its data
+                                directory or a single *.data file); a tag
+                                naming an activity (or else the first one,
+                                creating it) picks the activity, the others
+                                become tags; imported intervals are left out
+  import csv <file> [--map <rules>] [--ask]
+                                import a CSV export of Toggl or Clockify;
+                                projects map to activities by rules (e.g.
+                                `--map 'Web*=MainJob; Private=-'`, `-`
+                                leaving them out), unmatched ones to the
+                                activity of the same name or, w/ `--ask`, to
+                                the one you enter; imported entries are left
+                                out
+  activities                    list active and deactivated activities
+  activities add <name>
+  activities deactivate <activity>
+  activities reactivate <activity>
+  activities rename <activity> <name>
+  activities merge <activity> <into>
+                                move all entries of an activity into another
+                                one and remove it
+  activities delete <activity> [--yes]
+                                permanently delete an activity and its
+                                entries (asks for `--yes` first)
+  projects                      list clients, projects and their activities
+  projects add <name> [client]
+  projects assign <activity> <project | none>
+  projects remove <project>     (its activities are kept)
+  clients add <name>
+  clients remove <client>       (its projects are kept)
+  help                          print this message";
+
+/// run the subcommand given by `args` (program name already stripped)
+pub fn run(db : &mut Connection, args : &[String])
+    -> Result<(), Box<dyn error::Error>>
+{
+    let mut args : Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    let tags = take_option(&mut args, "--tags")?
+        .map(db::tags::parse)
+        .unwrap_or_default();
+
+    if !tags.is_empty() && !matches!(args.first(), Some(&"start" | &"add"))
+    {
+        return Err("--tags is only supported by start and add".into());
+    }
+
+    let note = take_option(&mut args, "--note")?;
+
+    if note.is_some() &&
+        !matches!(args.first(), Some(&"start" | &"add" | &"stop"))
+    {
+        return Err("--note is only supported by start, stop and add".into());
+    }
+
+    // stats restricted to a tag run on a filtered copy of the db
+    if let Some(tag) = take_option(&mut args, "--tag")?
+    {
+        if args.first() != Some(&"stats")
+        {
+            return Err("--tag is only supported by stats".into());
+        }
+        return run(&mut db::tags::filtered(db, tag)?,
+                   &args.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    }
+
+    // date range report (or range of an export)
+    let from = take_option(&mut args, "--from")?;
+    let to = take_option(&mut args, "--to")?;
+    let delimiter = take_option(&mut args, "--delimiter")?;
+    let duplicates = take_option(&mut args, "--duplicates")?;
+    let map = take_option(&mut args, "--map")?;
+
+    if delimiter.is_some() && args.first() != Some(&"export")
+    {
+        return Err("--delimiter is only supported by export".into());
+    }
+    if duplicates.is_some() && args.first() != Some(&"import")
+    {
+        return Err("--duplicates is only supported by import".into());
+    }
+    if map.is_some() && args.get(..2) != Some(&["import", "csv"])
+    {
+        return Err("--map is only supported by import csv".into());
+    }
+
+    if args.first() == Some(&"export")
+    {
+        let today = Local::now().date_naive();
+        let date = |input : Option<&str>| input
+            .map(|d| parse_date(d, today)
+                 .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday"))
+            .transpose();
+
+        return export(db, &args[1..], date(from)?, date(to)?, delimiter);
+    }
+    if args.first() == Some(&"import")
+    {
+        if from.is_some() || to.is_some()
+        {
+            return Err("--from and --to aren't supported by import".into());
+        }
+        return import(db, &args[1..], duplicates, map);
+    }
+
+    if from.is_some() || to.is_some()
+    {
+        if args.as_slice() != ["stats"]
+        {
+            return Err("--from and --to are only supported by stats and \
+                        export".into());
+        }
+
+        let today = Local::now().date_naive();
+        let from = parse_date(from.ok_or("--to requires --from")?, today)
+            .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+        let to = parse_date(to.unwrap_or("today"), today)
+            .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+
+        return db::stat::printstats_range(db, from, to);
+    }
+
+    match args.as_slice()
+    {
+        ["start", activity] => start(db, activity, &tags, note),
+        ["stop"] => stop(db, None, note),
+        ["stop", "--at", at] => stop(db, Some(at), note),
+        ["stop", "--discard"] => {
+            let timer = db::timer::discard(db)?;
+            println!("Discarded timer for {}",
+                     db::get_activityname_for_id(db, timer.id)?);
+            Ok(())
+        },
+        ["status"] => status(db),
+        ["add", activity, duration] =>
+            add(db, activity, duration, "today", &tags, note),
+        ["add", activity, duration, date] =>
+            add(db, activity, duration, date, &tags, note),
+        ["delete", activity, date] => delete(db, activity, date),
+        ["sessions"] => sessions(db, "today", "today"),
+        ["sessions", "delete", sid] => {
+            let sid : i64 = sid.parse().map_err(|_| "Invalid session id")?;
+            db::sessions::remove(db, sid)?;
+            println!("Session removed");
+            Ok(())
+        },
+        ["sessions", "note", sid] => {
+            let sid : i64 = sid.parse().map_err(|_| "Invalid session id")?;
+            db::sessions::set_note(db, sid, None)?;
+            println!("Note removed");
+            Ok(())
+        },
+        ["sessions", "note", sid, note] => {
+            let sid : i64 = sid.parse().map_err(|_| "Invalid session id")?;
+            db::sessions::set_note(db, sid, Some(note))?;
+            println!("Note set");
+            Ok(())
+        },
+        ["sessions", "tag", sid, tags] => {
+            let sid : i64 = sid.parse().map_err(|_| "Invalid session id")?;
+            db::sessions::get_by_sid(db, sid)?;
+            db::tags::add(db, sid, &db::tags::parse(tags))?;
+            println!("Session tagged");
+            Ok(())
+        },
+        ["sessions", "untag", sid, tag] => {
+            let sid : i64 = sid.parse().map_err(|_| "Invalid session id")?;
+            db::tags::remove(db, sid, tag)?;
+            println!("Tag removed");
+            Ok(())
+        },
+        ["sessions", "split", sid, at] => {
+            let sid : i64 = sid.parse().map_err(|_| "Invalid session id")?;
+            let session = db::sessions::get_by_sid(db, sid)?;
+            let at = parse_datetime(at, session.started.date_naive())
+                .ok_or("Invalid time, use HH:MM or YYYY-MM-DD HH:MM")?;
+            let second = db::sessions::split(db, sid, &at)?;
+            println!("Session split, second part has id {}", second);
+            Ok(())
+        },
+        ["sessions", "reassign", sid, activity] => {
+            let sid : i64 = sid.parse().map_err(|_| "Invalid session id")?;
+            let id = find_any_activity(db, activity)?;
+            db::sessions::reassign(db, sid, id)?;
+            println!("Session reassigned");
+            Ok(())
+        },
+        ["sessions", from] => sessions(db, from, from),
+        ["sessions", from, to] => sessions(db, from, to),
+        ["history"] => history(db, None, None, None),
+        ["history", from] => history(db, Some(from), None, None),
+        ["history", from, to] => history(db, Some(from), Some(to), None),
+        ["history", from, to, activity] =>
+            history(db, Some(from), Some(to), Some(activity)),
+        ["edit", "set", activity, date, duration] => {
+            let id = find_any_activity(db, activity)?;
+            let date = parse_date(date, Local::now().date_naive())
+                .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+            let duration = parse_duration(duration)
+                .ok_or("Invalid duration, use HH:MM or decimal hours")?;
+            db::set_day_hours(db, id, date,
+                              duration.num_seconds() as f64 / 3600.)?;
+            println!("Entry set to {}", format_duration(&duration));
+            Ok(())
+        },
+        ["edit", "move", activity, into, date, duration] => {
+            let id = find_any_activity(db, activity)?;
+            let into = find_any_activity(db, into)?;
+            let date = parse_date(date, Local::now().date_naive())
+                .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+            let duration = parse_duration(duration)
+                .ok_or("Invalid duration, use HH:MM or decimal hours")?;
+            db::move_hours(db, id, into, date,
+                           duration.num_seconds() as f64 / 3600.)?;
+            println!("Moved {} to {}", format_duration(&duration),
+                     db::get_activityname_for_id(db, into)?);
+            Ok(())
+        },
+        ["stats"] => db::stat::printstats_detail(db, Some(false)),
+        ["stats", "--detailed"] => db::stat::printstats_detail(db, Some(true)),
+        ["stats", "--year"] => db::stat::printstats_year(db),
+        ["stats", period @ ("--weeks" | "--months"), year @ ..]
+            if year.len() <= 1 => {
+            let period = match *period
+            {
+                "--weeks" => db::stat::helpers::Period::Week,
+                _         => db::stat::helpers::Period::Month,
+            };
+            let year = year.first()
+                .map(|year| year.parse::<i32>().map_err(|_| "Invalid year"))
+                .transpose()?;
+            db::stat::printstats_series(db, period, year)
+        },
+        ["stats", "--weekdays"] => db::stat::printstats_weekdays(db),
+        ["stats", "--breaks", range @ ..] if range.len() <= 2 => {
+            let today = Local::now().date_naive();
+            let from = match range.first()
+            {
+                Some(from) => parse_date(from, today)
+                    .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?,
+                None => today - Duration::days(27),
+            };
+            let to = parse_date(range.get(1).unwrap_or(&"today"), today)
+                .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+            db::stat::printstats_breaks(db, from, to)
+        },
+        ["stats", "--hours", year @ ..] if year.len() <= 1 => {
+            let year = year.first()
+                .map(|year| year.parse::<i32>().map_err(|_| "Invalid year"))
+                .transpose()?;
+            db::stat::printstats_hours(db, year)
+        },
+        ["stats", "--heatmap"] =>
+            db::stat::printheatmap(db, Local::now().iso_week().year()),
+        ["stats", "--heatmap", year] => {
+            let year : i32 = year.parse().map_err(|_| "Invalid year")?;
+            db::stat::printheatmap(db, year)
+        },
+        ["stats", "--year", "--activity", activities] => {
+            let ids = activities.split(',')
+                .map(|a| find_any_activity(db, a.trim()))
+                .collect::<Result<Vec<_>, _>>()?;
+            db::stat::printstats_year_of(db, Some(&ids))
+        },
+        ["tags"] => {
+            if db::tags::get(db)?.is_empty() { println!("No tags in use"); }
+            db::stat::printtags(db)
+        },
+        ["thresholds"] => {
+            let format = |hours : Vec<f64>| hours.iter()
+                .map(|h| h.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!("Days : {}", format(db::settings::day_thresholds(db)?));
+            println!("Weeks: {}", format(db::settings::week_thresholds(db)?));
+            println!("Streak: {} (single activity: {})",
+                     db::settings::streak_hours(db)?,
+                     db::settings::streak_activity_hours(db)?);
+            Ok(())
+        },
+        ["thresholds", what, hours] => {
+            use db::settings::*;
+            let key = match *what
+            {
+                "days"            => KEY_DAY_THRESHOLDS,
+                "weeks"           => KEY_WEEK_THRESHOLDS,
+                "streak"          => KEY_STREAK_HOURS,
+                "streak-activity" => KEY_STREAK_ACTIVITY,
+                _ => return Err("Unknown kind of thresholds".into()),
+            };
+            if *hours == "default"
+            {
+                reset(db, key)?;
+            }
+            else if key == KEY_STREAK_HOURS || key == KEY_STREAK_ACTIVITY
+            {
+                set_threshold(db, key, hours)?;
+            }
+            else
+            {
+                set_thresholds(db, key, hours)?;
+            }
+            println!("Thresholds set");
+            Ok(())
+        },
+        ["goals"] => goals(db),
+        ["goals", "set", activity, period, hours] => {
+            let activity = goal_activity(db, activity)?;
+            let period = db::goals::Period::parse(period)
+                .ok_or("Period has to be day, week or month")?;
+            let hours = parse_duration(hours).ok_or("Invalid duration")?;
+            db::goals::set(db, activity, period,
+                           hours.num_seconds() as f64 / 3600.)?;
+            println!("Goal set");
+            Ok(())
+        },
+        ["goals", "remove", activity, period] => {
+            let activity = goal_activity(db, activity)?;
+            let period = db::goals::Period::parse(period)
+                .ok_or("Period has to be day, week or month")?;
+            db::goals::remove(db, activity, period)?;
+            println!("Goal removed");
+            Ok(())
+        },
+        ["activities"] => activities(db),
+        ["activities", "add", name] => {
+            db::add_activity(db, name)?;
+            println!("Activity added");
+            Ok(())
+        },
+        ["activities", "deactivate", activity] => {
+            let id = db::find_activity(db, activity, true)?;
+            db::deactivate_activity(db, id)?;
+            println!("Activity deactivated");
+            Ok(())
+        },
+        ["activities", "reactivate", activity] => {
+            let id = db::find_activity(db, activity, false)?;
+            db::reactivate_activity(db, id)?;
+            println!("Activity reactivated");
+            Ok(())
+        },
+        ["activities", "rename", activity, name] => {
+            let id = find_any_activity(db, activity)?;
+            db::rename_activity(db, id, name)?;
+            println!("Activity renamed");
+            Ok(())
+        },
+        ["activities", "merge", from, into] => {
+            let from = find_any_activity(db, from)?;
+            let into = find_any_activity(db, into)?;
+            let rows = db::merge_activities(db, from, into)?;
+            println!("Activity merged ({} days moved)", rows);
+            Ok(())
+        },
+        ["activities", "delete", activity] => {
+            let id = find_any_activity(db, activity)?;
+            println!("Deleting {} removes {} history entries (days) and its \
+                      sessions; rerun w/ --yes to do so",
+                     db::get_activityname_for_id(db, id)?,
+                     db::count_history_rows(db, id)?);
+            Ok(())
+        },
+        ["activities", "delete", activity, "--yes"] => {
+            let id = find_any_activity(db, activity)?;
+            let rows = db::delete_activity(db, id)?;
+            println!("Activity deleted ({} days removed)", rows);
+            Ok(())
+        },
+        ["projects"] => projects(db),
+        ["projects", "add", name] => {
+            db::projects::add_project(db, name, None)?;
+            println!("Project added");
+            Ok(())
+        },
+        ["projects", "add", name, client] => {
+            let client = db::projects::find_client(db, client)?;
+            db::projects::add_project(db, name, Some(client))?;
+            println!("Project added");
+            Ok(())
+        },
+        ["projects", "assign", activity, project] => {
+            let id = find_any_activity(db, activity)?;
+            let project = match *project
+            {
+                "none" => None,
+                project => Some(db::projects::find_project(db, project)?),
+            };
+            db::projects::assign(db, id, project)?;
+            println!("Activity assigned");
+            Ok(())
+        },
+        ["projects", "remove", project] => {
+            let id = db::projects::find_project(db, project)?;
+            db::projects::remove_project(db, id)?;
+            println!("Project removed");
+            Ok(())
+        },
+        ["clients", "add", name] => {
+            db::projects::add_client(db, name)?;
+            println!("Client added");
+            Ok(())
+        },
+        ["clients", "remove", client] => {
+            let id = db::projects::find_client(db, client)?;
+            db::projects::remove_client(db, id)?;
+            println!("Client removed");
+            Ok(())
+        },
+        ["help"] | ["--help"] | ["-h"] => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            Err("Invalid command or arguments".into())
+        },
+    }
+}
+
+fn start(
+    db       : &mut Connection,
+    activity : &str,
+    tags     : &[String],
+    note     : Option<&str>,
+    ) -> Result<(), Box<dyn error::Error>>
+{
+    let id = db::find_activity(db, activity, true)?;
+    let now = Local::now();
+
+    let tx = db.transaction()?;
+    db::timer::start(&tx, id, &now, false, tags)?;
+    db::timer::set_note(&tx, note)?;
+    tx.commit()?;
+
+    println!("Started timer for {} at {}",
+             db::get_activityname_for_id(db, id)?,
+             now.format("%Y-%m-%d %H:%M:%S"));
+
+    Ok(())
+}
+
+fn stop(db : &mut Connection, at : Option<&str>, note : Option<&str>)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let now = Local::now();
+    let end = match (at, db::timer::get(db)?)
+    {
+        (None, _) => now,
+        (Some(at), Some(timer)) =>
+            parse_datetime(at, timer.started.date_naive())
+            .ok_or("Invalid time, use HH:MM or YYYY-MM-DD HH:MM")?,
+        (Some(_), None) => return Err("No timer is running".into()),
+    };
+
+    if end > now
+    {
+        return Err("Can't stop a timer in the future".into());
+    }
+
+    if note.is_some()
+    {
+        db::timer::set_note(db, note)?;
+    }
+
+    let timer = db::timer::finish(db, &end)?;
+    let name = db::get_activityname_for_id(db, timer.id)?;
+
+    if timer.onbreak
+    {
+        println!("Stopped timer for {} (was on break)", name);
+    }
+    else
+    {
+        println!("Stopped timer for {}, worked: {}", name,
+                 format_duration(&end.signed_duration_since(timer.started)));
+    }
+
+    Ok(())
+}
+
+fn status(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
+{
+    match db::timer::get(db)?
+    {
+        Some(timer) => {
+            let duration = Local::now().signed_duration_since(timer.started);
+            println!("{}: {} since {} ({})",
+                     if timer.onbreak { "On break" } else { "Running" },
+                     db::get_activityname_for_id(db, timer.id)?,
+                     timer.started.format("%Y-%m-%d %H:%M:%S"),
+                     format_duration(&duration));
+            if let Some(note) = timer.note
+            {
+                println!("Note: {}", note);
+            }
+        },
+        None => println!("No timer running"),
+    }
+
+    Ok(())
+}
+
+fn add(
+    db       : &mut Connection,
+    activity : &str,
+    duration : &str,
+    date     : &str,
+    tags     : &[String],
+    note     : Option<&str>,
+    ) -> Result<(), Box<dyn error::Error>>
+{
+    let id = db::find_activity(db, activity, true)?;
+    let duration = parse_duration(duration)
+        .ok_or("Invalid duration, use HH:MM or decimal hours")?;
+    let date = parse_date(date, Local::now().date_naive())
+        .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+
+    if duration <= Duration::zero() || duration >= Duration::hours(24)
+    {
+        return Err("Duration has to be between 00:01 and 23:59".into());
+    }
+    if date > Local::now().date_naive()
+    {
+        return Err("Can't enter times for future dates".into());
+    }
+
+    // same as manual entry: start at midnight, add duration
+    let dtbeg = db::helpers::local_midnight(date)?;
+    let dtend = dtbeg + duration;
+
+    // all or nothing: the hours w/ their tags and note
+    let tx = db.transaction()?;
+    let sid = db::sessions::enter(&tx, &dtbeg, &dtend, id,
+                                  db::sessions::Kind::Work,
+                                  db::sessions::Source::Manual)?;
+    db::tags::add(&tx, sid, tags)?;
+    db::sessions::set_note(&tx, sid, note)?;
+    tx.commit()?;
+
+    println!("Added {} on {} for {}",
+             format_duration(&duration),
+             date.format("%Y-%m-%d"),
+             db::get_activityname_for_id(db, id)?);
+
+    Ok(())
+}
+
+fn delete(db : &mut Connection, activity : &str, date : &str)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let id = find_any_activity(db, activity)?;
+    let date = parse_date(date, Local::now().date_naive())
+        .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+
+    db::remove_from_db(db, &db::helpers::local_midnight(date)?, id)?;
+
+    println!("Entry removed");
+
+    Ok(())
+}
+
+fn sessions(db : &mut Connection, from : &str, to : &str)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let today = Local::now().date_naive();
+    let from = parse_date(from, today)
+        .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+    let to = parse_date(to, today)
+        .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?;
+
+    println!("ID\tDate       Begin-End    Hours  Kind   Source  Activity");
+
+    for session in db::sessions::get(db, from, to)?
+    {
+        let tags = db::tags::of_session(db, session.sid)?;
+
+        println!("{}\t{} {}-{} {:6.2}  {:<6} {:<7} {}{}{}",
+                 session.sid,
+                 session.started.format("%Y-%m-%d"),
+                 session.started.format("%H:%M"),
+                 session.ended.format("%H:%M"),
+                 session.hours(),
+                 session.kind.as_str(),
+                 session.source.as_str(),
+                 db::get_activityname_for_id(db, session.id)?,
+                 if tags.is_empty() { String::new() }
+                 else { format!(" [{}]", tags.join(", ")) },
+                 session.note.map(|n| format!(" - {}", n))
+                     .unwrap_or_default());
+    }
+
+    Ok(())
+}
+
+/// list history entries of a date range, optionally of one activity only
+fn history(
+    db       : &mut Connection,
+    from     : Option<&str>,
+    to       : Option<&str>,
+    activity : Option<&str>,
+    ) -> Result<(), Box<dyn error::Error>>
+{
+    let today = Local::now().date_naive();
+    let from = match from
+    {
+        Some(from) => parse_date(from, today)
+            .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?,
+        None => today - Duration::days(7),
+    };
+    let to = match to
+    {
+        Some(to) => parse_date(to, today)
+            .ok_or("Invalid date, use YYYY-MM-DD, today or yesterday")?,
+        None => today,
+    };
+    let id = activity.map(|a| find_any_activity(db, a)).transpose()?;
+
+    println!("Date        Hours  Activity");
+
+    for entry in db::retrieve_history(db, from, to, id)?
+    {
+        let date = NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d")?;
+        let notes = db::sessions::notes_of_day(db, entry.id, date)?;
+
+        println!("{}  {:5.2}  {}{}",
+                 entry.date, entry.hours,
+                 db::get_activityname_for_id(db, entry.id)?,
+                 if notes.is_empty() { String::new() }
+                 else { format!(" - {}", notes.join("; ")) });
+    }
+
+    Ok(())
+}
+
+fn activities(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
+{
+    println!("ID\tName");
+
+    for activity in db::get_activities(db, true)?
+    {
+        println!("{}\t{}", activity.id, activity.name);
+    }
+    for activity in db::get_activities(db, false)?
+    {
+        println!("{}\t{} (deactivated)", activity.id, activity.name);
+    }
+
+    Ok(())
+}
+
+/// export data to a file (`-` for stdout)
+fn export(
+    db        : &mut Connection,
+    args      : &[&str],
+    from      : Option<NaiveDate>,
+    to        : Option<NaiveDate>,
+    delimiter : Option<&str>,
+    )
+    -> Result<(), Box<dyn error::Error>>
+{
+    let (what, path) = match args
+    {
+        ["csv", what @ ("history" | "activities"), path] => (*what, *path),
+        [what @ ("json" | "ics"), path] => (*what, *path),
+        _ => return Err("Unknown export, see `timetracker help`".into()),
+    };
+
+    if !matches!(what, "history" | "ics") && (from.is_some() || to.is_some())
+    {
+        return Err("--from and --to are only supported by csv history and \
+                    ics".into());
+    }
+    if matches!(what, "json" | "ics") && delimiter.is_some()
+    {
+        return Err("--delimiter is only supported by csv".into());
+    }
+
+    let delimiter = db::export::parse_delimiter(delimiter.unwrap_or(","))?;
+    let mut out = open_output(path)?;
+
+    let exported = match what
+    {
+        "history" => format!("{} rows", db::export::history_csv(
+                db, &mut out, delimiter, from, to)?),
+        "activities" => format!("{} rows", db::export::activities_csv(
+                db, &mut out, delimiter)?),
+        "ics" => format!("{} events", db::export::ics(
+                db, &mut out, from, to)?),
+        _ => {
+            let (activities, sessions) = db::export::json(db, &mut out)?;
+            format!("{} activities and {} sessions", activities, sessions)
+        },
+    };
+    out.flush()?;
+
+    if path != "-" { println!("Exported {} to {}", exported, path); }
+
+    Ok(())
+}
+
+/// import data from a file (`-` for stdin)
+fn import(db : &mut Connection, args : &[&str], duplicates : Option<&str>,
+          map : Option<&str>)
+    -> Result<(), Box<dyn error::Error>>
+{
+    if let ["csv", path, rest @ ..] = args
+    {
+        let ask = match rest
+        {
+            []        => false,
+            ["--ask"] => true,
+            _         => return Err("Unknown import, see `timetracker help`"
+                                    .into()),
+        };
+        if duplicates.is_some()
+        {
+            return Err("--duplicates is only supported by json".into());
+        }
+        if ask && *path == "-"
+        {
+            return Err("--ask needs a file, stdin is for the answers".into());
+        }
+
+        let rules = db::import::parse_rules(map.unwrap_or_default())?;
+        let mut mapping = |project : &str| {
+            // w/o a matching rule: ask or the same name
+            match db::import::map_project(&rules, project)
+            {
+                Some(activity) => Ok(activity),
+                None if ask    => Ok(ask_activity(project)),
+                None           =>
+                    Ok(Some(project.to_string()).filter(|p| !p.is_empty())),
+            }
+        };
+
+        let report = db::import::csv(db, &mut open_input(path)?,
+                                     &mut mapping)?;
+
+        for skipped in &report.skipped { eprintln!("Skipped {}", skipped); }
+        println!("Imported: {} new activities, {} entries, {} imported \
+                  before, {} left out (unmapped projects), {} rows skipped",
+                 report.activities, report.intervals, report.duplicates,
+                 report.left_out, report.skipped.len());
+
+        return Ok(());
+    }
+
+    if let ["timewarrior", path] = args
+    {
+        if duplicates.is_some()
+        {
+            return Err("--duplicates is only supported by json".into());
+        }
+
+        let report = db::import::timewarrior(db, std::path::Path::new(path))?;
+
+        for skipped in &report.skipped { eprintln!("Skipped {}", skipped); }
+        println!("Imported: {} new activities, {} intervals, {} imported \
+                  before, {} lines skipped", report.activities,
+                 report.intervals, report.duplicates, report.skipped.len());
+
+        return Ok(());
+    }
+
+    let duplicates = db::import::Duplicates::parse(
+        duplicates.unwrap_or("skip"))
+        .ok_or("--duplicates has to be sum or skip")?;
+
+    let report = match args
+    {
+        ["json", path] =>
+            db::import::json(db, &mut open_input(path)?, duplicates)?,
+        _ => return Err("Unknown import, see `timetracker help`".into()),
+    };
+
+    println!("Imported: {} new activities, {} new entries, {} summed up, \
+              {} skipped", report.activities, report.entries, report.summed,
+             report.skipped);
+
+    Ok(())
+}
+
+/// prompt for the activity of a project of a CSV export (see import());
+/// `None` to leave its entries out
+fn ask_activity(project : &str) -> Option<String>
+{
+    let input = if project.is_empty()
+    {
+        crate::read_input("Activity of entries w/o project (empty to leave \
+                           them out): ")
+    }
+    else
+    {
+        crate::read_input(&format!("Activity of {} (empty for the same name, \
+                                    - to leave it out): ", project))
+    };
+
+    match input.as_str()
+    {
+        "-"      => None,
+        ""       => Some(project.to_string()).filter(|p| !p.is_empty()),
+        activity => Some(activity.to_string()),
+    }
+}
+
+fn open_output(path : &str)
+    -> Result<Box<dyn std::io::Write>, Box<dyn error::Error>>
+{
+    if path == "-" { return Ok(Box::new(std::io::stdout())); }
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Can't write {}: {}", path, e))?;
+
+    Ok(Box::new(std::io::BufWriter::new(file)))
+}
+
+fn open_input(path : &str)
+    -> Result<Box<dyn std::io::Read>, Box<dyn error::Error>>
+{
+    if path == "-" { return Ok(Box::new(std::io::stdin())); }
+
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Can't read {}: {}", path, e))?;
+
+    Ok(Box::new(std::io::BufReader::new(file)))
+}
+
+/// print all goals w/ the progress towards them
+fn goals(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
+{
+    let progress = db::goals::progress(db, Local::now().date_naive())?;
+
+    if progress.is_empty() { println!("No goals set"); }
+
+    for goal in progress
+    {
+        println!("{}", goal.describe());
+    }
+
+    Ok(())
+}
+
+/// activity of a goal given by the user; `all` for the overall goal
+fn goal_activity(db : &Connection, input : &str)
+    -> Result<Option<i32>, Box<dyn error::Error>>
+{
+    if input == "all" { return Ok(None); }
+
+    Ok(Some(db::find_activity(db, input, true)?))
+}
+
+/// print the client -> project -> activity hierarchy
+fn projects(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
+{
+    let clients  = db::projects::get_clients(db)?;
+    let projects = db::projects::get_projects(db)?;
+    let activities = db::get_all_activities(db)?;
+
+    let groups = clients.iter()
+        .map(|c| (Some(c.id), format!("{}\t{}", c.id, c.name)))
+        .chain(std::iter::once((None, "-\t(no client)".to_string())));
+
+    println!("ID\tName");
+
+    for (client, name) in groups
+    {
+        println!("{}", name);
+
+        for project in projects.iter().filter(|p| p.client == client)
+        {
+            println!("{}\t  {}", project.id, project.name);
+
+            let members = activities.iter()
+                .filter(|a| a.project == Some(project.id));
+
+            for act in members
+            {
+                println!("{}\t    {}{}", act.id, act.name,
+                         if act.active { "" } else { " (deactivated)" });
+            }
+        }
+    }
+
+    println!("-\t(no project)");
+
+    for act in activities.iter().filter(|a| a.project.is_none())
+    {
+        println!("{}\t    {}{}", act.id, act.name,
+                 if act.active { "" } else { " (deactivated)" });
+    }
+
+    Ok(())
+}
+
+/// remove `option <value>` from the arguments, returning the value
+fn take_option<'a>(args : &mut Vec<&'a str>, option : &str)
+    -> Result<Option<&'a str>, Box<dyn error::Error>>
+{
+    let index = match args.iter().position(|arg| *arg == option)
+    {
+        Some(index) => index,
+        None        => return Ok(None),
+    };
+
+    if index + 1 >= args.len()
+    {
+        return Err(format!("{} requires a value", option).into());
+    }
+
+    let value = args.remove(index + 1);
+    args.remove(index);
+
+    Ok(Some(value))
+}
+
+/// find an activity (id or name) among active and deactivated ones alike
+fn find_any_activity(db : &Connection, input : &str)
+    -> Result<i32, Box<dyn error::Error>>
+{
+    db::find_activity(db, input, true)
+        .or_else(|_| db::find_activity(db, input, false))
+}
+
+/// parse a duration given as `HH:MM` or as decimal hours (`1.5`)
+pub fn parse_duration(input : &str) -> Option<Duration>
+{
+    if let Some((hh, mm)) = input.split_once(':')
+    {
+        let hh : i64 = hh.parse().ok()?;
+        let mm : i64 = mm.parse().ok()?;
+        if hh < 0 || !(0..60).contains(&mm) { return None; }
+        return Some(Duration::hours(hh) + Duration::minutes(mm));
+    }
+
+    let hours : f64 = input.parse().ok()?;
+    if !hours.is_finite() || hours < 0. { return None; }
+    Some(Duration::seconds((hours * 3600.).round() as i64))
+}
+
+/// parse a date given as `YYYY-MM-DD`, `today` or `yesterday`
+pub fn parse_date(input : &str, today : NaiveDate) -> Option<NaiveDate>
+{
+    match input
+    {
+        "today"     => Some(today),
+        "yesterday" => today.pred_opt(),
+        _           => NaiveDate::parse_from_str(input, "%Y-%m-%d").ok(),
+    }
+}
+
+/// parse a point in time given as `YYYY-MM-DD HH:MM` or as `HH:MM` (on the
+/// given date)
+pub fn parse_datetime(input : &str, date : NaiveDate)
+    -> Option<DateTime<Local>>
+{
+    let naive = chrono::NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M")
+        .ok()
+        .or_else(|| {
+            chrono::NaiveTime::parse_from_str(input, "%H:%M")
+                .ok()
+                .map(|time| date.and_time(time))
+        })?;
+
+    Local.from_local_datetime(&naive).earliest()
+}
+
+fn format_duration(duration : &Duration) -> String
+{
+    format!("{:02}:{:02}:{:02}",
+            duration.num_hours(),
+            duration.num_minutes() % 60,
+            duration.num_seconds() % 60)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn parse_duration_works()
+    {
+        assert_eq!(parse_duration("1:30"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("0:05"), Some(Duration::minutes(5)));
+        assert_eq!(parse_duration("1.5"),  Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("8"),    Some(Duration::hours(8)));
+        assert_eq!(parse_duration("1:60"), None);
+        assert_eq!(parse_duration("-1"),   None);
+        assert_eq!(parse_duration("abc"),  None);
+    }
+
+    #[test]
+    fn parse_date_works()
+    {
+        let today = NaiveDate::from_ymd_opt(2024, 03, 01).unwrap();
+
+        assert_eq!(parse_date("today", today), Some(today));
+        assert_eq!(parse_date("yesterday", today),
+                   NaiveDate::from_ymd_opt(2024, 02, 29));
+        assert_eq!(parse_date("2023-12-24", today),
+                   NaiveDate::from_ymd_opt(2023, 12, 24));
+        assert_eq!(parse_date("24.12.2023", today), None);
+    }
+
+    #[test]
+    fn parse_datetime_works()
+    {
+        let date = NaiveDate::from_ymd_opt(2024, 03, 01).unwrap();
+
+        assert_eq!(parse_datetime("13:45", date),
+                   Local.with_ymd_and_hms(2024, 03, 01, 13, 45, 0).single());
+        assert_eq!(parse_datetime("2024-02-28 07:05", date),
+                   Local.with_ymd_and_hms(2024, 02, 28, 7, 5, 0).single());
+        assert_eq!(parse_datetime("25:00", date), None);
+    }
+}
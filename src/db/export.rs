@@ -0,0 +1,581 @@
+//! export of the db's data for use elsewhere (spreadsheets, another
+//! machine, calendars, ...); writers take any `io::Write`, so a file or
+//! stdout alike; the JSON document can be imported again (see import.rs)
+
+use std::collections::HashMap;
+use std::error;
+use std::io::Write;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use super::queries::*;
+use super::sessions::{self, Kind, Source};
+
+/// version of the JSON document layout; bump on incompatible changes
+/// (1: activities and history rows only, w/o times of day)
+pub const JSON_FORMAT : i32 = 2;
+
+/// timestamps of iCalendar events (UTC)
+const ICS_UTC_FORMAT : &str = "%Y%m%dT%H%M%SZ";
+
+/// JSON document: clients, projects, activities, all sessions (w/ notes and
+/// tags), goals and settings; `history` is only read from format 1
+/// documents (the history follows from the sessions)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Document {
+    pub format: i32,
+    pub exported: String,
+    #[serde(default)]
+    pub clients: Vec<Client>,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    pub activities: Vec<Activity>,
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+    #[serde(default)]
+    pub goals: Vec<Goal>,
+    #[serde(default)]
+    pub settings: Vec<Setting>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<Entry>,
+}
+
+/// a client of the JSON document
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Client {
+    pub name: String,
+    pub added: String,
+}
+
+/// a project of the JSON document, `client` by name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Project {
+    pub name: String,
+    pub client: Option<String>,
+    pub added: String,
+}
+
+/// an activity of the JSON document; `hourstotal` is informational, it
+/// follows from the sessions on import; `project` by name
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Activity {
+    pub name: String,
+    pub added: String,
+    pub hourstotal: f64,
+    pub active: bool,
+    pub archived: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+/// a session of the JSON document; `started` and `ended` as stored (UTC,
+/// see sessions::to_utc_string), `kind` and `source` as well
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub activity: String,
+    pub started: String,
+    pub ended: String,
+    pub kind: String,
+    pub source: String,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// a goal of the JSON document; `activity` by name, `None` for the overall
+/// goal
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Goal {
+    pub activity: Option<String>,
+    pub period: String,
+    pub hours: f64,
+}
+
+/// a setting of the JSON document (see db::settings)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+}
+
+/// a history row of a format 1 JSON document (hours of an activity on a
+/// date)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub activity: String,
+    pub date: String,
+    pub hours: f64,
+}
+
+/// write the history (a row per day and activity) from..=to (all time if
+/// not given) as CSV: date, ISO week, activity, hours and the notes of the
+/// day (separated by ` / `); returns the number of rows written
+pub fn history_csv(
+    db        : &Connection,
+    out       : &mut dyn Write,
+    delimiter : char,
+    from      : Option<NaiveDate>,
+    to        : Option<NaiveDate>,
+    )
+    -> Result<usize, Box<dyn error::Error>>
+{
+    let from = from.map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let to = to.map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "9999-12-31".to_string());
+
+    let mut stmt = db.prepare(
+        &format!("SELECT h.date, h.id, a.name, h.hoursonday
+                 FROM {} AS h JOIN {} AS a ON a.id = h.id
+                 WHERE h.date >= ?1 AND h.date <= ?2
+                 ORDER BY h.date ASC, a.name ASC",
+                 SQL_TABLEN_HIS, SQL_TABLEN_ACT))?;
+
+    let rows : Vec<(String, i32, String, f64)> = stmt
+        .query_map(params![from, to], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    write_csv_row(out, delimiter,
+                  &["date", "isoweek", "activity", "hours", "notes"])?;
+
+    for (date, id, name, hours) in &rows
+    {
+        let day = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+        let week = format!("{}-W{:02}",
+                           day.iso_week().year(), day.iso_week().week());
+        let notes = sessions::notes_of_day(db, *id, day)?.join(" / ");
+
+        write_csv_row(out, delimiter,
+                      &[date, &week, name, &super::helpers::round(*hours)
+                        .to_string(), &notes])?;
+    }
+
+    Ok(rows.len())
+}
+
+/// write all activities as CSV: id, name, active, date of deactivation,
+/// project, client, all time total and date of addition; returns the number
+/// of rows written
+pub fn activities_csv(db : &Connection, out : &mut dyn Write, delimiter : char)
+    -> Result<usize, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(
+        &format!("SELECT a.id, a.name, a.active, COALESCE(a.archived, ''),
+                     COALESCE(p.name, ''), COALESCE(c.name, ''),
+                     a.hourstotal, a.added
+                 FROM {} AS a
+                 LEFT JOIN {} AS p ON p.id = a.project
+                 LEFT JOIN {} AS c ON c.id = p.client
+                 ORDER BY a.id ASC",
+                 SQL_TABLEN_ACT, SQL_TABLEN_PRJ, SQL_TABLEN_CLI))?;
+
+    type Row = (i32, String, bool, String, String, String, f64, String);
+
+    let rows : Vec<Row> = stmt
+        .query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?,
+                row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    write_csv_row(out, delimiter,
+                  &["id", "name", "active", "archived", "project", "client",
+                    "hourstotal", "added"])?;
+
+    for (id, name, active, archived, project, client, total, added) in &rows
+    {
+        write_csv_row(out, delimiter,
+                      &[&id.to_string(), name, &active.to_string(), archived,
+                        project, client,
+                        &super::helpers::round(*total).to_string(), added])?;
+    }
+
+    Ok(rows.len())
+}
+
+/// retrieve all clients, projects, activities (active and deactivated),
+/// sessions, goals and settings as a JSON document
+pub fn document(db : &Connection) -> Result<Document, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(
+        &format!("SELECT name, added FROM {} ORDER BY id ASC",
+                 SQL_TABLEN_CLI))?;
+
+    let clients = stmt
+        .query_map([], |row| {
+            Ok(Client { name: row.get(0)?, added: row.get(1)? })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let mut stmt = db.prepare(
+        &format!("SELECT p.name, c.name, p.added
+                 FROM {} AS p LEFT JOIN {} AS c ON c.id = p.client
+                 ORDER BY p.id ASC", SQL_TABLEN_PRJ, SQL_TABLEN_CLI))?;
+
+    let projects = stmt
+        .query_map([], |row| {
+            Ok(Project {
+                name: row.get(0)?,
+                client: row.get(1)?,
+                added: row.get(2)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let mut stmt = db.prepare(
+        &format!("SELECT a.name, a.added, a.hourstotal, a.active, a.archived,
+                     p.name
+                 FROM {} AS a LEFT JOIN {} AS p ON p.id = a.project
+                 ORDER BY a.id ASC", SQL_TABLEN_ACT, SQL_TABLEN_PRJ))?;
+
+    let activities = stmt
+        .query_map([], |row| {
+            Ok(Activity {
+                name: row.get(0)?,
+                added: row.get(1)?,
+                hourstotal: super::helpers::round(row.get(2)?),
+                active: row.get(3)?,
+                archived: row.get(4)?,
+                project: row.get(5)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let mut stmt = db.prepare(
+        &format!("SELECT s.sid, a.name, s.started, s.ended, s.kind, s.source,
+                     s.note
+                 FROM {} AS s JOIN {} AS a ON a.id = s.id
+                 ORDER BY s.started ASC, s.sid ASC",
+                 SQL_TABLEN_SES, SQL_TABLEN_ACT))?;
+
+    let rows : Vec<(i64, Session)> = stmt
+        .query_map([], |row| {
+            Ok((row.get(0)?, Session {
+                activity: row.get(1)?,
+                started: row.get(2)?,
+                ended: row.get(3)?,
+                kind: row.get(4)?,
+                source: row.get(5)?,
+                note: row.get(6)?,
+                tags: Vec::new(),
+            }))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let mut sessions = Vec::new();
+
+    for (sid, mut session) in rows
+    {
+        session.tags = super::tags::of_session(db, sid)?;
+        sessions.push(session);
+    }
+
+    let mut goals = Vec::new();
+
+    for goal in super::goals::get(db)?
+    {
+        goals.push(Goal {
+            activity: match goal.activity
+            {
+                Some(id) => Some(super::get_activityname_for_id(db, id)?),
+                None     => None,
+            },
+            period: goal.period.as_str().to_string(),
+            hours: goal.hours,
+        });
+    }
+
+    let mut stmt = db.prepare(
+        &format!("SELECT key, value FROM {} ORDER BY key ASC",
+                 SQL_TABLEN_SET))?;
+
+    let settings = stmt
+        .query_map([], |row| {
+            Ok(Setting { key: row.get(0)?, value: row.get(1)? })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    Ok(Document {
+        format: JSON_FORMAT,
+        exported: chrono::Local::now().to_rfc3339(),
+        clients,
+        projects,
+        activities,
+        sessions,
+        goals,
+        settings,
+        history: Vec::new(),
+    })
+}
+
+/// write all data as JSON document (see document()); returns the number of
+/// activities and sessions written
+pub fn json(db : &Connection, out : &mut dyn Write)
+    -> Result<(usize, usize), Box<dyn error::Error>>
+{
+    let document = document(db)?;
+
+    serde_json::to_writer_pretty(&mut *out, &document)?;
+    writeln!(out)?;
+
+    Ok((document.activities.len(), document.sessions.len()))
+}
+
+/// write the work sessions of from..=to (all time if not given) as
+/// iCalendar events: summary the activity, description the note, categories
+/// the tags; tracked (and imported) sessions keep their times, manual ones
+/// (w/o times of day, see sessions::Source) become all-day events w/ the
+/// hours in the summary; returns the number of events written
+pub fn ics(
+    db   : &Connection,
+    out  : &mut dyn Write,
+    from : Option<NaiveDate>,
+    to   : Option<NaiveDate>,
+    )
+    -> Result<usize, Box<dyn error::Error>>
+{
+    let today = Local::now().date_naive();
+    let from = from.unwrap_or_else(|| NaiveDate::from_ymd_opt(1970, 1, 1)
+                                   .unwrap());
+    let to = to.unwrap_or(today);
+
+    let sessions : Vec<_> = sessions::get(db, from, to)?
+        .into_iter()
+        .filter(|s| s.kind == Kind::Work)
+        .collect();
+
+    let stamp = Utc::now().format(ICS_UTC_FORMAT).to_string();
+    let names : HashMap<i32, String> = super::get_all_activities(db)?
+        .into_iter()
+        .map(|activity| (activity.id, activity.name))
+        .collect();
+
+    write_ics_line(out, "BEGIN:VCALENDAR")?;
+    write_ics_line(out, "VERSION:2.0")?;
+    write_ics_line(out, "PRODID:-//timetracker//timetracker//EN")?;
+    write_ics_line(out, "CALSCALE:GREGORIAN")?;
+
+    for session in &sessions
+    {
+        write_ics_line(out, "BEGIN:VEVENT")?;
+        write_ics_line(out, &format!("UID:session-{}@timetracker",
+                                     session.sid))?;
+        write_ics_line(out, &format!("DTSTAMP:{}", stamp))?;
+
+        match session.source
+        {
+            Source::Timer | Source::Import => {
+                let utc = |time : &DateTime<Local>| time.with_timezone(&Utc)
+                    .format(ICS_UTC_FORMAT).to_string();
+                write_ics_line(out, &format!("DTSTART:{}",
+                                             utc(&session.started)))?;
+                write_ics_line(out, &format!("DTEND:{}",
+                                             utc(&session.ended)))?;
+                write_ics_line(out, &format!(
+                        "SUMMARY:{}", ics_text(&names[&session.id])))?;
+            },
+            Source::Manual | Source::Legacy => {
+                let day = session.started.date_naive();
+                write_ics_line(out, &format!("DTSTART;VALUE=DATE:{}",
+                                             day.format("%Y%m%d")))?;
+                write_ics_line(out, &format!(
+                        "DTEND;VALUE=DATE:{}",
+                        (day + Duration::days(1)).format("%Y%m%d")))?;
+                write_ics_line(out, &format!(
+                        "SUMMARY:{}", ics_text(&format!(
+                            "{} ({}h)", names[&session.id],
+                            super::helpers::round(session.hours())))))?;
+            },
+        }
+
+        if let Some(note) = &session.note
+        {
+            write_ics_line(out, &format!("DESCRIPTION:{}", ics_text(note)))?;
+        }
+
+        let tags = super::tags::of_session(db, session.sid)?;
+        if !tags.is_empty()
+        {
+            let tags : Vec<String> = tags.iter().map(|t| ics_text(t))
+                .collect();
+            write_ics_line(out, &format!("CATEGORIES:{}", tags.join(",")))?;
+        }
+
+        write_ics_line(out, "TRANSP:TRANSPARENT")?;
+        write_ics_line(out, "END:VEVENT")?;
+    }
+
+    write_ics_line(out, "END:VCALENDAR")?;
+
+    Ok(sessions.len())
+}
+
+/// text value of an iCalendar property (backslash, `;`, `,` and line breaks
+/// escaped)
+fn ics_text(text : &str) -> String
+{
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\n', '\r'], "\\n")
+}
+
+/// one iCalendar content line, folded after 75 octets (continuation lines
+/// begin w/ a space) and ended by CRLF as the standard demands
+fn write_ics_line(out : &mut dyn Write, line : &str)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let mut length = 0;
+
+    for c in line.chars()
+    {
+        if length + c.len_utf8() > 75
+        {
+            out.write_all(b"\r\n ")?;
+            length = 1;
+        }
+        write!(out, "{}", c)?;
+        length += c.len_utf8();
+    }
+    out.write_all(b"\r\n")?;
+
+    Ok(())
+}
+
+/// parse a delimiter given by the user: a single character or `tab`
+pub fn parse_delimiter(input : &str) -> Result<char, Box<dyn error::Error>>
+{
+    let mut chars = input.chars();
+
+    match (input, chars.next(), chars.next())
+    {
+        ("tab" | "\\t", _, _) => Ok('\t'),
+        (_, Some('"' | '\n' | '\r'), _) =>
+            Err("Delimiter can't be a quote or a line break".into()),
+        (_, Some(c), None) => Ok(c),
+        _ => Err("Delimiter has to be a single character (or tab)".into()),
+    }
+}
+
+/// one CSV line; fields containing the delimiter, quotes or line breaks are
+/// quoted (quotes within doubled)
+fn write_csv_row(out : &mut dyn Write, delimiter : char, fields : &[&str])
+    -> Result<(), Box<dyn error::Error>>
+{
+    let line = fields.iter()
+        .map(|field| {
+            if field.contains([delimiter, '"', '\n', '\r'])
+            {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            }
+            else
+            {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string());
+
+    writeln!(out, "{}", line)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use chrono::TimeZone;
+    use crate::test;
+
+    #[test]
+    fn csv_export()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        test::populate_db_w_data(&mut db);
+        crate::db::rename_activity(&db, 2, "B; \"the\" second").unwrap();
+
+        let from = NaiveDate::from_ymd_opt(2024, 01, 02);
+        let mut out = Vec::new();
+        assert_eq!(history_csv(&db, &mut out, ';', from, from).unwrap(), 4);
+
+        let csv = String::from_utf8(out).unwrap();
+        let lines : Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "date;isoweek;activity;hours;notes");
+        assert_eq!(lines[1], "2024-01-02;2024-W01;A;1.5;");
+        assert_eq!(lines[2],
+                   "2024-01-02;2024-W01;\"B; \"\"the\"\" second\";1.5;");
+
+        // all time
+        let mut out = Vec::new();
+        let rows = history_csv(&db, &mut out, ',', None, None).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), rows + 1);
+
+        let mut out = Vec::new();
+        assert_eq!(activities_csv(&db, &mut out, '\t').unwrap(), 4);
+        let csv = String::from_utf8(out).unwrap();
+        assert!(csv.lines().nth(1).unwrap().starts_with("1\tA\ttrue\t\t\t\t"));
+
+        assert_eq!(parse_delimiter("tab").unwrap(), '\t');
+        assert_eq!(parse_delimiter(";").unwrap(), ';');
+        assert!(parse_delimiter(";;").is_err());
+        assert!(parse_delimiter("\"").is_err());
+    }
+
+    #[test]
+    fn ics_export()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+
+        let midnight = Local.with_ymd_and_hms(2024, 03, 04, 0, 0, 0).unwrap();
+        sessions::enter(&db, &midnight, &(midnight + Duration::minutes(90)),
+                        2, Kind::Work, Source::Manual).unwrap();
+
+        let started = Local.with_ymd_and_hms(2024, 03, 05, 9, 0, 0).unwrap();
+        let ended = Local.with_ymd_and_hms(2024, 03, 05, 10, 30, 0).unwrap();
+        let sid = sessions::enter(&db, &started, &ended, 1, Kind::Work,
+                                  Source::Timer).unwrap();
+        let note = format!("review; parser, \"v2\"\n{}", "a".repeat(60));
+        sessions::set_note(&db, sid, Some(&note)).unwrap();
+        crate::db::tags::add(&db, sid, &["code".to_string()]).unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2024, 03, 04);
+        let to = NaiveDate::from_ymd_opt(2024, 03, 05);
+        let mut out = Vec::new();
+        let events = ics(&db, &mut out, day, to).unwrap();
+
+        let ics = String::from_utf8(out).unwrap();
+        let lines : Vec<&str> = ics.split("\r\n").collect();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(lines.iter().filter(|l| **l == "BEGIN:VEVENT").count(),
+                   events);
+        assert!(lines.iter().all(|l| l.len() <= 75));
+
+        // the tracked session keeps its times
+        let utc = |t : DateTime<Local>| t.with_timezone(&Utc)
+            .format(ICS_UTC_FORMAT).to_string();
+        let at = lines.iter()
+            .position(|l| *l == format!("DTSTART:{}", utc(started)))
+            .unwrap();
+        assert_eq!(lines[at + 1], format!("DTEND:{}", utc(ended)));
+        assert_eq!(lines[at + 2], "SUMMARY:A");
+        // escaped and folded after 75 octets
+        assert_eq!(lines[at + 3], format!("DESCRIPTION:review\\; parser\\, \
+                                           \"v2\"\\n{}", "a".repeat(39)));
+        assert_eq!(lines[at + 4], format!(" {}", "a".repeat(21)));
+        assert_eq!(lines[at + 5], "CATEGORIES:code");
+
+        // manual ones are all-day events
+        assert_eq!(lines[at - 8], "DTSTART;VALUE=DATE:20240304");
+        assert_eq!(lines[at - 7], "DTEND;VALUE=DATE:20240305");
+        assert_eq!(lines[at - 6], "SUMMARY:B (1.5h)");
+        assert_eq!(events, 2);
+    }
+}
@@ -0,0 +1,338 @@
+//! hour goals per day, week (ISO, Monday to Sunday) or month; either of a
+//! single activity or overall (of all activities together); progress towards
+//! them is computed from the history table
+
+use std::error;
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::{Connection, params};
+use super::queries::*;
+
+/// activity id denoting the overall goal in the goals table
+const OVERALL : i32 = 0;
+
+/// period a goal's hours are to be reached in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+/// representing a row from Goals table; `activity` is `None` for the overall
+/// goal
+#[derive(Debug, Clone, PartialEq)]
+pub struct Goal {
+    pub activity: Option<i32>,
+    pub period: Period,
+    pub hours: f64,
+}
+
+/// progress towards a goal within its current period
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub goal: Goal,
+    pub name: String,    // activity name, `Overall` for the overall goal
+    pub done: f64,       // hours of the current period thus far
+    pub days_left: i64,  // days of the current period left, today included
+}
+
+impl Period
+{
+    pub fn as_str(&self) -> &'static str
+    {
+        match self
+        {
+            Period::Day   => "day",
+            Period::Week  => "week",
+            Period::Month => "month",
+        }
+    }
+
+    pub fn parse(s : &str) -> Option<Period>
+    {
+        match s
+        {
+            "day"   | "d" => Some(Period::Day),
+            "week"  | "w" => Some(Period::Week),
+            "month" | "m" => Some(Period::Month),
+            _             => None,
+        }
+    }
+
+    /// first and last day of the period `date` is in
+    pub fn range(&self, date : NaiveDate) -> (NaiveDate, NaiveDate)
+    {
+        match self
+        {
+            Period::Day   => (date, date),
+            Period::Week  => {
+                let monday = date - Duration::days(
+                    date.weekday().num_days_from_monday() as i64);
+                (monday, monday + Duration::days(6))
+            },
+            Period::Month => {
+                let first = date.with_day(1).unwrap();
+                let days = super::helpers::days_in_month(date.year(),
+                                                         date.month());
+                (first, first + Duration::days(days as i64 - 1))
+            },
+        }
+    }
+
+    /// most hours a goal of the period can have; for months those of the
+    /// shortest one (February), a goal applying to every month
+    pub fn max_hours(&self) -> f64
+    {
+        match self
+        {
+            Period::Day   => 24.,
+            Period::Week  => 7. * 24.,
+            Period::Month => 28. * 24.,
+        }
+    }
+
+    fn label(&self) -> &'static str
+    {
+        match self
+        {
+            Period::Day   => "today",
+            Period::Week  => "this week",
+            Period::Month => "this month",
+        }
+    }
+}
+
+impl Progress
+{
+    /// hours still missing to reach the goal
+    pub fn togo(&self) -> f64
+    {
+        (self.goal.hours - self.done).max(0.)
+    }
+
+    /// hours needed a day (today included) to reach the goal in time
+    pub fn per_day(&self) -> f64
+    {
+        self.togo() / self.days_left.max(1) as f64
+    }
+
+    /// e.g. `A 31.50/40h this week, 8.50h to go, 1.70h/day needed for the
+    /// remaining 5 days`
+    pub fn describe(&self) -> String
+    {
+        let mut line = format!("{} {:.2}/{}h {}", self.name, self.done,
+                               self.goal.hours, self.goal.period.label());
+
+        if self.togo() <= 0.
+        {
+            line.push_str(", goal reached");
+        }
+        else
+        {
+            line.push_str(&format!(", {:.2}h to go", self.togo()));
+
+            if self.days_left > 1
+            {
+                line.push_str(&format!(
+                        ", {:.2}h/day needed for the remaining {} days",
+                        self.per_day(), self.days_left));
+            }
+        }
+
+        line
+    }
+
+    /// short form for the live timer, `extra` being the hours of the running
+    /// timer (not yet entered), e.g. `A 31.72/40h week`
+    pub fn short(&self, extra : f64) -> String
+    {
+        format!("{} {:.2}/{}h {}", self.name, self.done + extra,
+                self.goal.hours, self.goal.period.as_str())
+    }
+}
+
+/// retrieve all goals, ordered by activity (overall goal first) and period
+pub fn get(db : &Connection) -> Result<Vec<Goal>, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(
+        &format!("SELECT activity, period, hours FROM {} ORDER BY activity",
+                 SQL_TABLEN_GOL))?;
+
+    let rows : Vec<(i32, String, f64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut goals = Vec::new();
+
+    for (activity, period, hours) in rows
+    {
+        let period = Period::parse(&period)
+            .ok_or_else(|| format!("Invalid goal period in db: {}", period))?;
+
+        goals.push(Goal {
+            activity: if activity == OVERALL { None } else { Some(activity) },
+            period,
+            hours,
+        });
+    }
+
+    goals.sort_by_key(|g| (g.activity, g.period));
+
+    Ok(goals)
+}
+
+/// set the goal of an activity (`None`: overall) for a period, replacing a
+/// previous one
+pub fn set(db : &Connection, activity : Option<i32>, period : Period,
+           hours : f64)
+    -> Result<(), Box<dyn error::Error>>
+{
+    if !hours.is_finite() || hours <= 0.
+    {
+        return Err("Goals have to be positive hours".into());
+    }
+    if hours > period.max_hours()
+    {
+        return Err(format!("A {} has no more than {} hours (goals apply to \
+                            every {0})", period.as_str(), period.max_hours())
+                   .into());
+    }
+    if let Some(id) = activity
+    {
+        super::get_activityname_for_id(db, id)?;
+    }
+
+    db.execute(
+        &format!("INSERT OR REPLACE INTO {} (activity, period, hours)
+                 VALUES (?1, ?2, ?3)", SQL_TABLEN_GOL),
+        params![activity.unwrap_or(OVERALL), period.as_str(), hours])?;
+
+    Ok(())
+}
+
+/// remove the goal of an activity (`None`: overall) for a period
+pub fn remove(db : &Connection, activity : Option<i32>, period : Period)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let changed = db.execute(
+        &format!("DELETE FROM {} WHERE activity = ?1 AND period = ?2",
+                 SQL_TABLEN_GOL),
+        params![activity.unwrap_or(OVERALL), period.as_str()])?;
+
+    if changed == 0
+    {
+        return Err("No such goal".into());
+    }
+
+    Ok(())
+}
+
+/// remove all goals of an activity (it's deleted or merged into another one)
+pub fn remove_all_of(db : &Connection, activity : i32)
+    -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(&format!("DELETE FROM {} WHERE activity = ?1", SQL_TABLEN_GOL),
+               params![activity])?;
+
+    Ok(())
+}
+
+/// progress towards all goals of active activities (and the overall ones) as
+/// of `today`
+pub fn progress(db : &Connection, today : NaiveDate)
+    -> Result<Vec<Progress>, Box<dyn error::Error>>
+{
+    let activities = super::get_activities(db, true)?;
+    let mut progress = Vec::new();
+
+    for goal in get(db)?
+    {
+        let name = match goal.activity
+        {
+            None     => "Overall".to_string(),
+            Some(id) => match activities.iter().find(|a| a.id == id)
+            {
+                Some(activity) => activity.name.clone(),
+                None           => continue, // deactivated
+            },
+        };
+
+        let (from, to) = goal.period.range(today);
+        let done : f64 = db.query_row(
+            &format!("SELECT COALESCE(SUM(hoursonday), 0.0) FROM {}
+                     WHERE date >= ?1 AND date <= ?2
+                     AND (?3 IS NULL OR id = ?3)", SQL_TABLEN_HIS),
+            params![from.format("%Y-%m-%d").to_string(),
+                    today.format("%Y-%m-%d").to_string(),
+                    goal.activity],
+            |row| row.get(0))?;
+
+        progress.push(Progress {
+            name,
+            done,
+            days_left: (to - today).num_days() + 1,
+            goal,
+        });
+    }
+
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn goals_and_progress()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        test::populate_db_w_data(&mut db);
+        let epsilon = 0.001;
+
+        set(&db, Some(1), Period::Week, 40.).unwrap();
+        set(&db, Some(1), Period::Day, 4.).unwrap();
+        set(&db, None, Period::Month, 300.).unwrap();
+        set(&db, Some(1), Period::Week, 20.).unwrap();
+        assert!(set(&db, Some(42), Period::Week, 1.).is_err());
+        assert!(set(&db, None, Period::Day, 25.).is_err());
+        assert!(set(&db, None, Period::Week, 169.).is_err());
+        assert!(set(&db, None, Period::Month, 700.).is_err());
+        assert!(set(&db, None, Period::Day, -1.).is_err());
+
+        let goals = get(&db).unwrap();
+        assert_eq!(goals.len(), 3);
+        assert_eq!(goals[0].activity, None);
+        assert_eq!(goals[1].period, Period::Day);
+        assert!((goals[2].hours - 20.).abs() <= epsilon);
+
+        // Wednesday; Monday and Tuesday w/ 2.5 hours a day per activity
+        let today = NaiveDate::from_ymd_opt(2024, 02, 14).unwrap();
+        let progress = progress(&db, today).unwrap();
+
+        // the month thus far: Feb 1st only 5 hours (the rest was entered on
+        // Jan 31st), then 13 days w/ 10 hours (today included)
+        assert_eq!(progress[0].name, "Overall");
+        assert!((progress[0].done - 135.).abs() <= epsilon);
+        assert_eq!(progress[0].days_left, 16);
+        assert!((progress[0].per_day() - 165. / 16.).abs() <= epsilon);
+
+        assert!((progress[1].done - 2.5).abs() <= epsilon);
+        assert!((progress[1].togo() - 1.5).abs() <= epsilon);
+        assert_eq!(progress[1].days_left, 1);
+
+        assert!((progress[2].done - 7.5).abs() <= epsilon);
+        assert_eq!(progress[2].days_left, 5);
+        assert_eq!(progress[2].describe(),
+                   "A 7.50/20h this week, 12.50h to go, \
+                   2.50h/day needed for the remaining 5 days");
+
+        remove(&db, Some(1), Period::Day).unwrap();
+        assert!(remove(&db, Some(1), Period::Day).is_err());
+        remove_all_of(&db, 1).unwrap();
+        assert_eq!(get(&db).unwrap().len(), 1);
+    }
+}
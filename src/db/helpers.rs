@@ -1,5 +1,5 @@
 use regex::Regex;
-use chrono::{Datelike, DateTime, Local, TimeZone};
+use chrono::{Datelike, DateTime, Duration, Local, NaiveDate, TimeZone};
 use rusqlite::{Connection, params};
 
 // helper function to clean a sql query
@@ -44,6 +44,63 @@ pub fn days_in_year(year : i32) -> u32
     }
 }
 
+// helper function to split a time span into the hours attributed to the day
+// of its beginning and (when past midnight) the day of its end; the returned
+// vector holds one or two entries; spans of >= 24 hours aren't supported
+pub fn hours_per_day(dtbeg : &DateTime<Local>, dtend : &DateTime<Local>)
+    -> Vec<(NaiveDate, f64)>
+{
+    // signed_duration_since works with the internal UTC time of both
+    // DateTime<Local> objects; it'll adjust for DST & timezone updates!
+    let durationhours =
+        dtend.signed_duration_since(dtbeg).num_seconds() as f64 / 3600.;
+
+    if dtbeg.date_naive() < dtend.date_naive()
+    {
+        // important to test for `<` and not `!=` here
+        // in strict theory dtend.date_naive() can be before dtbeg.date_naive()
+        // (consider taking a flight slightly past midnight, flying eastward
+        // such that you land "on the previous day")
+        // in such a case we'll simply ignore the date change
+
+        // we work w/ date_naive()! here which ignores localtime updates
+
+        let nextmidnight = (dtbeg.naive_local() + Duration::days(1))
+            .date()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        let lastmidnight = dtend.date_naive().and_hms_opt(0, 0, 0).unwrap();
+
+        let tillmidnight =
+            (nextmidnight - dtbeg.naive_local()).num_seconds() as f64 / 3600.;
+
+        let mut frommidnight =
+            (dtend.naive_local() - lastmidnight).num_seconds() as f64 / 3600.;
+
+        // if their sum is greater than previously computed duration
+        // we adjust the end time to reflect the UTC offset change which occured
+
+        if tillmidnight + frommidnight > durationhours {
+            frommidnight -= tillmidnight + frommidnight - durationhours;
+        }
+
+        return vec![(dtbeg.date_naive(), tillmidnight),
+                    (dtend.date_naive(), frommidnight)];
+    }
+
+    vec![(dtbeg.date_naive(), durationhours)]
+}
+
+// helper function to construct the (first) local midnight of a date
+pub fn local_midnight(date : NaiveDate)
+    -> Result<DateTime<Local>, Box<dyn std::error::Error>>
+{
+    Local.from_local_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+        .earliest()
+        .ok_or_else(|| "Invalid local date".into())
+}
+
 pub fn retrieve_first_entry_ymd(db : &mut Connection) -> DateTime<Local>
 {
     let firstentry_str : String = db.query_row(
@@ -83,6 +140,22 @@ mod tests
         assert_eq!(days_in_month(2025, 02), 28);
     }
 
+    #[test]
+    fn hours_per_day_works()
+    {
+        let beg = Local.with_ymd_and_hms(2024, 01, 10, 22, 30, 0).unwrap();
+
+        let split = hours_per_day(&beg, &(beg + Duration::hours(1)));
+        assert_eq!(split.len(), 1);
+        assert!((split[0].1 - 1.).abs() <= 0.001);
+
+        let split = hours_per_day(&beg, &(beg + Duration::hours(3)));
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[1].0, NaiveDate::from_ymd_opt(2024, 01, 11).unwrap());
+        assert!((split[0].1 - 1.5).abs() <= 0.001);
+        assert!((split[1].1 - 1.5).abs() <= 0.001);
+    }
+
     #[test]
     fn days_in_year_works()
     {
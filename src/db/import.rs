@@ -0,0 +1,1252 @@
+//! import of data exported elsewhere (see export.rs for the JSON document)
+//! or recorded by other time trackers (Timewarrior, CSV exports of Toggl or
+//! Clockify); activities are matched by name, imported intervals are entered
+//! as sessions of source `import`, day totals (w/o times of day) as manual
+//! ones (history and totals follow as for any other session)
+
+use std::collections::HashMap;
+use std::error;
+use std::io::Read;
+use std::path::Path;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime,
+             NaiveTime};
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use super::export::{self, Document};
+use super::queries::*;
+use super::sessions::{self, Kind, Source};
+
+/// what to do w/ a row for a day an activity already has hours on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Duplicates {
+    Sum,  // add the imported hours
+    Skip, // keep the existing hours only
+}
+
+/// outcome of an import
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub activities: usize, // newly created
+    pub entries: usize,    // days w/o previous hours
+    pub summed: usize,     // days w/ previous hours, hours added
+    pub skipped: usize,    // days w/ previous hours, left as they were
+}
+
+/// outcome of an import of intervals (see timewarrior() and csv())
+#[derive(Debug, Default, PartialEq)]
+pub struct IntervalReport {
+    pub activities: usize,    // newly created
+    pub intervals: usize,     // entered as sessions
+    pub duplicates: usize,    // imported before, left out
+    pub left_out: usize,      // of projects mapped to no activity (csv())
+    pub skipped: Vec<String>, // `file:line: reason` of lines left out
+}
+
+/// what became of an interval (see enter_interval())
+enum Entered {
+    Session { new_activity: bool },
+    Duplicate, // imported before
+}
+
+/// a rule mapping projects of a CSV export to an activity; `*` in the
+/// pattern matches any text (e.g. `Website*`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub pattern: String,
+    pub activity: String,
+}
+
+/// an interval tracked elsewhere
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub started: DateTime<Local>,
+    pub ended: DateTime<Local>,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+}
+
+/// activity of a project of a CSV export (see csv()), `None` to leave its
+/// entries out
+pub type Mapping<'a> = dyn FnMut(&str)
+    -> Result<Option<String>, Box<dyn error::Error>> + 'a;
+
+impl Duplicates
+{
+    pub fn parse(s : &str) -> Option<Duplicates>
+    {
+        match s
+        {
+            "sum"  => Some(Duplicates::Sum),
+            "skip" => Some(Duplicates::Skip),
+            _      => None,
+        }
+    }
+}
+
+/// import a JSON document (see export::json()) into the db, be it empty
+/// (restore) or not (merge); all or nothing: any invalid row aborts the
+/// import w/o changes; clients, projects and activities are matched by
+/// name, sessions of a day an activity already has hours on are entered or
+/// not depending on `duplicates`, goals and settings only where the db has
+/// none of its own; format 1 documents have day totals instead of sessions
+pub fn json(db : &Connection, input : &mut dyn Read, duplicates : Duplicates)
+    -> Result<Report, Box<dyn error::Error>>
+{
+    let document : Document = serde_json::from_reader(input)
+        .map_err(|e| format!("Invalid JSON document: {}", e))?;
+
+    if document.format != 1 && document.format != export::JSON_FORMAT
+    {
+        return Err(format!("Unsupported JSON document format {} (expected \
+                            1 to {})", document.format, export::JSON_FORMAT)
+                   .into());
+    }
+
+    let mut report = Report::default();
+    let tx = db.unchecked_transaction()?;
+
+    json_activities(&tx, &document, &mut report)?;
+
+    if document.format == 1
+    {
+        json_history(&tx, &document.history, duplicates, &mut report)?;
+    }
+    else
+    {
+        json_sessions(&tx, &document.sessions, duplicates, &mut report)?;
+        json_goals_settings(&tx, &document)?;
+    }
+
+    tx.commit()?;
+
+    Ok(report)
+}
+
+/// create the clients, projects and activities of a JSON document the db
+/// doesn't have yet; existing activities w/o project get the one of the
+/// document
+fn json_activities(db : &Connection, document : &Document,
+                   report : &mut Report)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let check_date = |name : &str, added : &str| {
+        NaiveDate::parse_from_str(added, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date added of {}: {}", name, added))
+    };
+
+    for client in &document.clients
+    {
+        check_date(&client.name, &client.added)?;
+
+        if find_in(db, SQL_TABLEN_CLI, &client.name)?.is_none()
+        {
+            db.execute(
+                &format!("INSERT INTO {} (name, added) VALUES (?1, ?2)",
+                         SQL_TABLEN_CLI),
+                params![client.name.trim(), client.added])?;
+        }
+    }
+
+    for project in &document.projects
+    {
+        check_date(&project.name, &project.added)?;
+
+        let client = match &project.client
+        {
+            Some(name) => Some(find_in(db, SQL_TABLEN_CLI, name)?
+                .ok_or_else(|| format!("Client of project not in document: \
+                                        {}", name))?),
+            None => None,
+        };
+
+        if find_in(db, SQL_TABLEN_PRJ, &project.name)?.is_none()
+        {
+            db.execute(
+                &format!("INSERT INTO {} (name, client, added)
+                         VALUES (?1, ?2, ?3)", SQL_TABLEN_PRJ),
+                params![project.name.trim(), client, project.added])?;
+        }
+    }
+
+    for activity in &document.activities
+    {
+        check_date(&activity.name, &activity.added)?;
+
+        let project = match &activity.project
+        {
+            Some(name) => Some(find_in(db, SQL_TABLEN_PRJ, name)?
+                .ok_or_else(|| format!("Project of activity not in \
+                                        document: {}", name))?),
+            None => None,
+        };
+
+        match find_by_name(db, &activity.name)?
+        {
+            Some(id) =>
+            {
+                db.execute(
+                    &format!("UPDATE {} SET project = ?1
+                             WHERE id = ?2 AND project IS NULL",
+                             SQL_TABLEN_ACT),
+                    params![project, id])?;
+            },
+            None =>
+            {
+                db.execute(
+                    &format!("INSERT INTO {} (name, added, active, archived,
+                                 project)
+                             VALUES (?1, ?2, ?3, ?4, ?5)", SQL_TABLEN_ACT),
+                    params![activity.name.trim(), activity.added,
+                            activity.active, activity.archived, project])?;
+                report.activities += 1;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// enter the day totals of a format 1 JSON document
+fn json_history(db : &Connection, history : &[export::Entry],
+                duplicates : Duplicates, report : &mut Report)
+    -> Result<(), Box<dyn error::Error>>
+{
+    for entry in history
+    {
+        let id = find_by_name(db, &entry.activity)?
+            .ok_or_else(|| format!("Activity of entry not in document: {}",
+                                   entry.activity))?;
+        let date = NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date of entry: {}", entry.date))?;
+
+        match (entry_hours(db, id, date, entry.hours, duplicates)?,
+               duplicates)
+        {
+            (false, _)               => report.entries += 1,
+            (true, Duplicates::Sum)  => report.summed += 1,
+            (true, Duplicates::Skip) => report.skipped += 1,
+        }
+    }
+
+    Ok(())
+}
+
+/// enter the sessions of a JSON document w/ their notes and tags; the report
+/// counts days (of an activity) as for format 1 documents
+fn json_sessions(db : &Connection, sessions : &[export::Session],
+                 duplicates : Duplicates, report : &mut Report)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let mut parsed = Vec::new();
+
+    // whether a day of an activity had hours before the import
+    let mut existing : HashMap<(i32, NaiveDate), bool> = HashMap::new();
+
+    for session in sessions
+    {
+        let id = find_by_name(db, &session.activity)?
+            .ok_or_else(|| format!("Activity of session not in document: {}",
+                                   session.activity))?;
+        let started = sessions::from_utc_string(&session.started)
+            .map_err(|_| format!("Invalid start of session: {}",
+                                 session.started))?;
+        let ended = sessions::from_utc_string(&session.ended)
+            .map_err(|_| format!("Invalid end of session: {}",
+                                 session.ended))?;
+        let kind = Kind::parse(&session.kind)
+            .ok_or_else(|| format!("Invalid kind of session: {}",
+                                   session.kind))?;
+        let source = Source::parse(&session.source)
+            .ok_or_else(|| format!("Invalid source of session: {}",
+                                   session.source))?;
+
+        let day = (id, started.date_naive());
+        existing.entry(day)
+            .or_insert_with(|| super::hours_on_day(db, id, day.1) > 0.);
+
+        parsed.push((day, started, ended, kind, source, session));
+    }
+
+    for existed in existing.values()
+    {
+        match (existed, duplicates)
+        {
+            (false, _)               => report.entries += 1,
+            (true, Duplicates::Sum)  => report.summed += 1,
+            (true, Duplicates::Skip) => report.skipped += 1,
+        }
+    }
+
+    for (day, started, ended, kind, source, session) in parsed
+    {
+        if existing[&day] && duplicates == Duplicates::Skip { continue; }
+
+        let sid = sessions::enter(db, &started, &ended, day.0, kind, source)
+            .map_err(|e| format!("Session of {} at {}: {}",
+                                 session.activity, session.started, e))?;
+        sessions::set_note(db, sid, session.note.as_deref())?;
+        super::tags::add(db, sid, &session.tags)?;
+    }
+
+    Ok(())
+}
+
+/// take over the goals and settings of a JSON document the db has none of
+/// its own of
+fn json_goals_settings(db : &Connection, document : &Document)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let goals = super::goals::get(db)?;
+
+    for goal in &document.goals
+    {
+        let activity = match &goal.activity
+        {
+            Some(name) => Some(find_by_name(db, name)?
+                .ok_or_else(|| format!("Activity of goal not in document: \
+                                        {}", name))?),
+            None => None,
+        };
+        let period = super::goals::Period::parse(&goal.period)
+            .ok_or_else(|| format!("Invalid period of goal: {}",
+                                   goal.period))?;
+
+        if !goals.iter().any(|g| g.activity == activity && g.period == period)
+        {
+            super::goals::set(db, activity, period, goal.hours)?;
+        }
+    }
+
+    for setting in &document.settings
+    {
+        if super::settings::get(db, &setting.key)?.is_none()
+        {
+            super::settings::set(db, &setting.key, &setting.value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// enter `hours` of an activity on a date as manual session (beginning at
+/// midnight, they've no times of day; stats of times of day and the calendar
+/// export rely on import sessions having real ones), unless the day already
+/// has hours and those are to be kept; returns whether the day already had
+/// hours
+pub fn entry_hours(
+    db         : &Connection,
+    actid      : i32,
+    date       : NaiveDate,
+    hours      : f64,
+    duplicates : Duplicates,
+    )
+    -> Result<bool, Box<dyn error::Error>>
+{
+    if !hours.is_finite() || hours < 0.
+    {
+        return Err(format!("Invalid hours on {}: {}", date, hours).into());
+    }
+
+    let existing = super::hours_on_day(db, actid, date) > 0.;
+
+    if hours == 0. || (existing && duplicates == Duplicates::Skip)
+    {
+        return Ok(existing);
+    }
+
+    let started = super::helpers::local_midnight(date)?;
+    let ended = started + Duration::seconds((hours * 3600.).round() as i64);
+
+    sessions::enter(db, &started, &ended, actid, Kind::Work, Source::Manual)
+        .map_err(|e| format!("Entry on {}: {}", date, e))?;
+
+    Ok(existing)
+}
+
+/// import the intervals of Timewarrior data files (`data/*.data`); `path` is
+/// either a single file or the data directory; the first tag of an interval
+/// naming an activity decides its activity (w/o any the first tag becomes a
+/// new activity), all other tags become tags of the session, an annotation
+/// its note; intervals imported before are left out, so are open and
+/// invalid ones (reported w/ their line)
+pub fn timewarrior(db : &Connection, path : &Path)
+    -> Result<IntervalReport, Box<dyn error::Error>>
+{
+    let mut files = Vec::new();
+
+    if path.is_dir()
+    {
+        for entry in std::fs::read_dir(path)?
+        {
+            let file = entry?.path();
+            if file.extension().is_some_and(|ext| ext == "data")
+            {
+                files.push(file);
+            }
+        }
+        files.sort();
+    }
+    else
+    {
+        files.push(path.to_path_buf());
+    }
+
+    if files.is_empty()
+    {
+        return Err(format!("No *.data files in {}", path.display()).into());
+    }
+
+    let mut report = IntervalReport::default();
+    let mut tx = db.unchecked_transaction()?;
+
+    for file in files
+    {
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("Can't read {}: {}", file.display(), e))?;
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+
+        for (index, line) in content.lines().enumerate()
+        {
+            let interval = match parse_timewarrior_line(line)
+            {
+                Ok(Some(interval)) => Ok(interval),
+                Ok(None)           => continue,
+                Err(err)           => Err(err),
+            };
+
+            enter_or_skip(&mut tx, &mut report,
+                          format!("{}:{}", name, index + 1), |db| {
+                let interval = interval?;
+                let activity = tags_activity(db, &interval.tags)?;
+                enter_interval(db, &interval, &activity)
+            })?;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(report)
+}
+
+/// parse a line of a Timewarrior data file, e.g.
+/// `inc 20240115T080000Z - 20240115T120000Z # MainJob "code review" # "notes"`;
+/// `None` for empty lines
+pub fn parse_timewarrior_line(line : &str)
+    -> Result<Option<Interval>, Box<dyn error::Error>>
+{
+    let tokens = tokenize(line)?;
+
+    let rest = match tokens.split_first()
+    {
+        None => return Ok(None),
+        Some(((keyword, false), rest)) if keyword == "inc" => rest,
+        Some(_) => return Err("Not an interval".into()),
+    };
+
+    let time = |token : &(String, bool)| -> Result<_, Box<dyn error::Error>>
+    {
+        let time = NaiveDateTime::parse_from_str(&token.0, "%Y%m%dT%H%M%SZ")
+            .map_err(|_| format!("Invalid time: {}", token.0))?;
+        Ok(time.and_utc().with_timezone(&Local))
+    };
+
+    let (started, ended, rest) = match rest
+    {
+        [beg, (dash, false), end, rest @ ..] if dash == "-" =>
+            (time(beg)?, time(end)?, rest),
+        [beg, rest @ ..] if rest.first().is_none_or(|t| t.0 == "#") => {
+            time(beg)?;
+            return Err("Open interval (still tracking)".into());
+        },
+        _ => return Err("Invalid interval".into()),
+    };
+
+    // `# tags [# "annotation"]`
+    let (tags, note) = match rest
+    {
+        [] => (&rest[..0], None),
+        [(hash, false), rest @ ..] if hash == "#" => {
+            match rest.iter().position(|t| *t == ("#".to_string(), false))
+            {
+                Some(pos) => (&rest[..pos], rest.get(pos + 1)
+                              .map(|t| t.0.clone())),
+                None      => (rest, None),
+            }
+        },
+        _ => return Err("Invalid interval".into()),
+    };
+
+    Ok(Some(Interval {
+        started,
+        ended,
+        tags: tags.iter().map(|t| t.0.clone()).collect(),
+        note: note.filter(|n| !n.is_empty()),
+    }))
+}
+
+/// split a line at whitespace, double quotes group (w/ backslash escapes);
+/// tokens come w/ whether they were quoted
+fn tokenize(line : &str) -> Result<Vec<(String, bool)>, Box<dyn error::Error>>
+{
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next()
+    {
+        if c.is_whitespace() { continue; }
+
+        if c == '"'
+        {
+            let mut token = String::new();
+            loop
+            {
+                match chars.next()
+                {
+                    Some('"')  => break,
+                    Some('\\') => token.extend(chars.next()),
+                    Some(c)    => token.push(c),
+                    None       => return Err("Unterminated quote".into()),
+                }
+            }
+            tokens.push((token, true));
+        }
+        else
+        {
+            let mut token = c.to_string();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace())
+            {
+                token.push(c);
+            }
+            tokens.push((token, false));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// import the entries of a CSV export of Toggl or Clockify (columns found by
+/// their header: project, description, start date/time, end date/time or
+/// duration, tags); `mapping` picks the activity of a project (created if
+/// there's none of that name), `None` leaves its entries out; it's asked
+/// once per project; the description becomes the note, tags become tags;
+/// entries imported before are left out, so are invalid ones (reported w/
+/// their row)
+pub fn csv(
+    db      : &Connection,
+    input   : &mut dyn Read,
+    mapping : &mut Mapping<'_>,
+    )
+    -> Result<IntervalReport, Box<dyn error::Error>>
+{
+    let mut content = String::new();
+    input.read_to_string(&mut content)?;
+    let content = content.trim_start_matches('\u{feff}');
+
+    // the delimiter most frequent in the header
+    let header = content.lines().next().unwrap_or_default();
+    let delimiter = [',', ';', '\t'].into_iter()
+        .max_by_key(|d| header.matches(*d).count())
+        .unwrap_or(',');
+
+    let rows = csv_records(content, delimiter)?;
+    let (header, rows) = rows.split_first().ok_or("Empty CSV file")?;
+
+    let column = |names : &[&str]| header.iter()
+        .position(|h| names.contains(&h.trim().to_lowercase().as_str()));
+
+    let columns = CsvColumns {
+        project: column(&["project"]).ok_or("No project column")?,
+        description: column(&["description"]),
+        start_date: column(&["start date"]).ok_or("No start date column")?,
+        start_time: column(&["start time"]).ok_or("No start time column")?,
+        end_date: column(&["end date"]),
+        end_time: column(&["end time"]),
+        duration: column(&["duration", "duration (h)",
+                           "duration (decimal)"]),
+        tags: column(&["tags"]),
+    };
+
+    if columns.end_time.is_none() && columns.duration.is_none()
+    {
+        return Err("Neither an end time nor a duration column".into());
+    }
+
+    let mut activities : HashMap<String, Option<String>> = HashMap::new();
+    let mut report = IntervalReport::default();
+    let mut tx = db.unchecked_transaction()?;
+
+    for (index, row) in rows.iter().enumerate()
+    {
+        if row.iter().all(|field| field.trim().is_empty()) { continue; }
+
+        let project = row.get(columns.project)
+            .map(|p| p.trim())
+            .unwrap_or_default();
+
+        if !activities.contains_key(project)
+        {
+            activities.insert(project.to_string(), mapping(project)?);
+        }
+
+        let activity = match &activities[project]
+        {
+            None           => {
+                report.left_out += 1;
+                continue;
+            },
+            Some(activity) => activity,
+        };
+
+        // the header being row 1
+        enter_or_skip(&mut tx, &mut report, format!("row {}", index + 2),
+                      |db| enter_interval(db, &parse_csv_row(row, &columns)?,
+                                          activity))?;
+    }
+
+    tx.commit()?;
+
+    Ok(report)
+}
+
+/// indices of the columns of a CSV export (see csv())
+struct CsvColumns {
+    project: usize,
+    description: Option<usize>,
+    start_date: usize,
+    start_time: usize,
+    end_date: Option<usize>,
+    end_time: Option<usize>,
+    duration: Option<usize>,
+    tags: Option<usize>,
+}
+
+/// an interval from a row of a CSV export; w/o an end time it ends after
+/// the duration, w/o an end date on the start date (or the next day if the
+/// end time is before the start time)
+fn parse_csv_row(row : &[String], columns : &CsvColumns)
+    -> Result<Interval, Box<dyn error::Error>>
+{
+    let field = |index : Option<usize>| index
+        .and_then(|i| row.get(i))
+        .map(|f| f.trim())
+        .filter(|f| !f.is_empty());
+
+    let start_date = parse_csv_date(field(Some(columns.start_date))
+                                    .ok_or("No start date")?)?;
+    let started = start_date.and_time(
+        parse_csv_time(field(Some(columns.start_time))
+                       .ok_or("No start time")?)?);
+
+    let ended = match (field(columns.end_time), field(columns.duration))
+    {
+        (Some(time), _) => {
+            let time = parse_csv_time(time)?;
+            match field(columns.end_date)
+            {
+                Some(date) => parse_csv_date(date)?.and_time(time),
+                None if time < started.time() =>
+                    (start_date + Duration::days(1)).and_time(time),
+                None => start_date.and_time(time),
+            }
+        },
+        (None, Some(duration)) => started + parse_csv_duration(duration)
+            .ok_or_else(|| format!("Invalid duration: {}", duration))?,
+        (None, None) => return Err("Neither an end time nor a duration".into()),
+    };
+
+    let local = |time : NaiveDateTime| time.and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| format!("Invalid local time: {}", time));
+
+    Ok(Interval {
+        started: local(started)?,
+        ended: local(ended)?,
+        // comma separated, tags may contain spaces
+        tags: field(columns.tags)
+            .map(|tags| tags.split(',')
+                 .map(|tag| tag.trim().to_string())
+                 .filter(|tag| !tag.is_empty())
+                 .collect())
+            .unwrap_or_default(),
+        note: field(columns.description).map(|d| d.to_string()),
+    })
+}
+
+/// dates as exported by Toggl (`2024-01-15`) and Clockify (depending on its
+/// settings `01/15/2024` or `15.01.2024`)
+fn parse_csv_date(input : &str) -> Result<NaiveDate, Box<dyn error::Error>>
+{
+    ["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y"].iter()
+        .find_map(|format| NaiveDate::parse_from_str(input, format).ok())
+        .ok_or_else(|| format!("Invalid date: {}", input).into())
+}
+
+/// times w/ or w/o seconds, 24 hour or AM/PM (e.g. `09:30:00 PM`)
+fn parse_csv_time(input : &str) -> Result<NaiveTime, Box<dyn error::Error>>
+{
+    ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"].iter()
+        .find_map(|format| NaiveTime::parse_from_str(input, format).ok())
+        .ok_or_else(|| format!("Invalid time: {}", input).into())
+}
+
+/// durations as `HH:MM:SS` (or `HH:MM`) or as decimal hours
+fn parse_csv_duration(input : &str) -> Option<Duration>
+{
+    let parts : Vec<&str> = input.split(':').collect();
+
+    match parts.as_slice()
+    {
+        [hours] => hours.parse::<f64>().ok()
+            .filter(|h| h.is_finite() && *h >= 0.)
+            .map(|h| Duration::seconds((h * 3600.).round() as i64)),
+        [hours, minutes, seconds @ ..] if seconds.len() <= 1 => {
+            let seconds = seconds.first().map_or(Some(0), |s| s.parse().ok());
+            Some(Duration::hours(hours.parse().ok()?)
+                 + Duration::minutes(minutes.parse().ok()?)
+                 + Duration::seconds(seconds?))
+        },
+        _ => None,
+    }
+}
+
+/// split CSV content into records of fields; quoted fields may contain the
+/// delimiter, line breaks and doubled quotes
+fn csv_records(content : &str, delimiter : char)
+    -> Result<Vec<Vec<String>>, Box<dyn error::Error>>
+{
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next()
+    {
+        match c
+        {
+            '"' if quoted && chars.next_if_eq(&'"').is_some() =>
+                field.push('"'),
+            '"' => quoted = !quoted,
+            c if quoted => field.push(c),
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' => {},
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            },
+            c => field.push(c),
+        }
+    }
+
+    if quoted
+    {
+        return Err("Unterminated quote in CSV file".into());
+    }
+    if !field.is_empty() || !record.is_empty()
+    {
+        record.push(field);
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// parse mapping rules, e.g. `Website*=MainJob; Internal=Admin`; an
+/// activity `-` leaves the entries of matching projects out
+pub fn parse_rules(input : &str) -> Result<Vec<Rule>, Box<dyn error::Error>>
+{
+    input.split(';')
+        .filter(|rule| !rule.trim().is_empty())
+        .map(|rule| match rule.split_once('=')
+        {
+            Some((pattern, activity)) if !activity.trim().is_empty() =>
+                Ok(Rule {
+                    pattern: pattern.trim().to_string(),
+                    activity: activity.trim().to_string(),
+                }),
+            _ => Err(format!("Invalid rule (project=activity): {}",
+                             rule.trim()).into()),
+        })
+        .collect()
+}
+
+/// the activity of the first rule matching a project (`None` w/o any);
+/// `Some(None)` if it's to be left out
+pub fn map_project(rules : &[Rule], project : &str) -> Option<Option<String>>
+{
+    rules.iter()
+        .find(|rule| matches_pattern(&rule.pattern, project))
+        .map(|rule| Some(rule.activity.clone()).filter(|a| a != "-"))
+}
+
+/// whether `text` matches `pattern` (`*` matching any text, case sensitive)
+fn matches_pattern(pattern : &str, text : &str) -> bool
+{
+    match pattern.split_once('*')
+    {
+        None => pattern == text,
+        Some((prefix, rest)) => match text.strip_prefix(prefix)
+        {
+            None       => false,
+            Some(text) => (0..=text.len())
+                .filter(|i| text.is_char_boundary(*i))
+                .any(|i| matches_pattern(rest, &text[i..])),
+        },
+    }
+}
+
+/// name of the activity an interval's tags pick (see timewarrior()): the
+/// first tag naming an activity, or else the first tag
+fn tags_activity(db : &Connection, tags : &[String])
+    -> Result<String, Box<dyn error::Error>>
+{
+    for tag in tags
+    {
+        if let Some(id) = find_by_name(db, tag)?
+        {
+            return super::get_activityname_for_id(db, id);
+        }
+    }
+
+    tags.first()
+        .map(|tag| tag.trim().to_string())
+        .ok_or_else(|| "Interval w/o tags".into())
+}
+
+/// enter an interval (see enter_interval()) within a savepoint: if it
+/// fails, it leaves nothing behind and is reported as skipped at `location`
+fn enter_or_skip(
+    tx       : &mut Transaction,
+    report   : &mut IntervalReport,
+    location : String,
+    enter    : impl FnOnce(&Connection)
+        -> Result<Entered, Box<dyn error::Error>>,
+    )
+    -> Result<(), Box<dyn error::Error>>
+{
+    let savepoint = tx.savepoint()?;
+
+    match enter(&savepoint)
+    {
+        Ok(entered) => {
+            savepoint.commit()?;
+            match entered
+            {
+                Entered::Session { new_activity } => {
+                    report.activities += new_activity as usize;
+                    report.intervals += 1;
+                },
+                Entered::Duplicate => report.duplicates += 1,
+            }
+        },
+        // rolled back when dropped
+        Err(err) => report.skipped.push(format!("{}: {}", location, err)),
+    }
+
+    Ok(())
+}
+
+/// enter an interval as import session of an activity (created if there's
+/// none of that name), unless it has been imported before; tags other than
+/// the activity's name become tags of the session
+fn enter_interval(db : &Connection, interval : &Interval, activity : &str)
+    -> Result<Entered, Box<dyn error::Error>>
+{
+    if interval.ended > Local::now()
+    {
+        return Err("Interval ends in the future".into());
+    }
+    // before creating the activity
+    sessions::check(&interval.started, &interval.ended, Kind::Work)?;
+
+    let activity = activity.trim();
+    if activity.is_empty()
+    {
+        return Err("Interval w/o activity".into());
+    }
+
+    let (actid, new_activity) = match find_by_name(db, activity)?
+    {
+        Some(id) => (id, false),
+        None     => {
+            super::add_activity(db, activity)?;
+            (db.last_insert_rowid() as i32, true)
+        },
+    };
+
+    let imported : i32 = db.query_row(
+        &format!("SELECT COUNT(*) FROM {} WHERE id = ?1 AND started = ?2
+                 AND ended = ?3 AND source = ?4", SQL_TABLEN_SES),
+        params![actid, sessions::to_utc_string(&interval.started),
+                sessions::to_utc_string(&interval.ended),
+                Source::Import.as_str()],
+        |row| row.get(0))?;
+
+    if imported > 0
+    {
+        return Ok(Entered::Duplicate);
+    }
+
+    let sid = sessions::enter(db, &interval.started, &interval.ended, actid,
+                              Kind::Work, Source::Import)?;
+
+    let tags : Vec<String> = interval.tags.iter()
+        .filter(|t| t.trim() != activity)
+        .cloned()
+        .collect();
+    super::tags::add(db, sid, &tags)?;
+    sessions::set_note(db, sid, interval.note.as_deref())?;
+
+    Ok(Entered::Session { new_activity })
+}
+
+/// id of the activity (active or not) of exactly that name
+pub fn find_by_name(db : &Connection, name : &str)
+    -> Result<Option<i32>, Box<dyn error::Error>>
+{
+    find_in(db, SQL_TABLEN_ACT, name)
+}
+
+/// id of the row of `table` (activities, projects or clients) named `name`
+fn find_in(db : &Connection, table : &str, name : &str)
+    -> Result<Option<i32>, Box<dyn error::Error>>
+{
+    let id = db.query_row(
+        &format!("SELECT id FROM {} WHERE name = ?1", table),
+        params![name.trim()],
+        |row| row.get(0))
+        .optional()?;
+
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test;
+
+    fn total(db : &Connection) -> f64
+    {
+        db.query_row(&format!("SELECT SUM(hourstotal) FROM {}", SQL_TABLEN_ACT),
+                     [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn timewarrior_lines()
+    {
+        let at = |s : &str| NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S")
+            .unwrap().and_utc().with_timezone(&Local);
+
+        let interval = parse_timewarrior_line(
+            "inc 20240115T080000Z - 20240115T120000Z # MainJob \"code \
+             review\" # \"parser \\\"v2\\\"\"").unwrap().unwrap();
+        assert_eq!(interval, Interval {
+            started: at("20240115T080000"),
+            ended: at("20240115T120000"),
+            tags: vec!["MainJob".to_string(), "code review".to_string()],
+            note: Some("parser \"v2\"".to_string()),
+        });
+
+        let interval = parse_timewarrior_line(
+            "inc 20240115T080000Z - 20240115T120000Z").unwrap().unwrap();
+        assert!(interval.tags.is_empty());
+
+        assert!(parse_timewarrior_line("  ").unwrap().is_none());
+        assert!(parse_timewarrior_line("inc 20240115T080000Z # A").is_err());
+        assert!(parse_timewarrior_line("inc 2024-01-15 - 2024-01-16").is_err());
+        assert!(parse_timewarrior_line("exc 20240115T080000Z").is_err());
+        assert!(parse_timewarrior_line("inc 20240115T080000Z - \"").is_err());
+    }
+
+    #[test]
+    fn timewarrior_import()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        let dir = std::env::temp_dir()
+            .join(format!("timetracker-timew-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("2024-01.data"),
+            "inc 20240110T080000Z - 20240110T100000Z # review B # \"notes\"\n\
+             inc 20240110T100000Z - 20240110T103000Z # Meetings\n\
+             inc 20240111T080000Z - 20240111T090000Z\n\
+             nonsense\n\
+             inc 20240112T080000Z - 20240113T090000Z # Forgotten\n").unwrap();
+        std::fs::write(dir.join("2024-02.data"),
+            "inc 20240201T080000Z - 20240201T090000Z # Meetings\n\
+             inc 20240202T080000Z\n").unwrap();
+        std::fs::write(dir.join("tags.json"), "{}").unwrap();
+
+        let report = timewarrior(&db, &dir).unwrap();
+        assert_eq!((report.activities, report.intervals, report.duplicates),
+                   (1, 3, 0));
+        assert_eq!(report.skipped.len(), 4);
+        assert!(report.skipped[0].starts_with("2024-01.data:3: "));
+
+        // rejected intervals don't leave their activity behind
+        assert!(report.skipped[2].starts_with("2024-01.data:5: "));
+        assert!(find_by_name(&db, "Forgotten").unwrap().is_none());
+
+        // B (not the first tag) decides, the rest are tags
+        let day = NaiveDate::from_ymd_opt(2024, 01, 10).unwrap();
+        let sessions = sessions::get(&db, day, day).unwrap();
+        assert!((sessions[0].hours() - 2.).abs() <= epsilon);
+        assert_eq!(sessions[0].id, 2);
+        assert_eq!(sessions[0].note.as_deref(), Some("notes"));
+        assert_eq!(crate::db::tags::of_session(&db, sessions[0].sid).unwrap(),
+                   vec!["review"]);
+
+        let meetings = find_by_name(&db, "Meetings").unwrap().unwrap();
+        let total : f64 = db.query_row(
+            &format!("SELECT hourstotal FROM {} WHERE id = ?1",
+                     SQL_TABLEN_ACT),
+            params![meetings], |row| row.get(0)).unwrap();
+        assert!((total - 1.5).abs() <= epsilon);
+
+        // importing again leaves everything as it was
+        let report = timewarrior(&db, &dir).unwrap();
+        assert_eq!((report.intervals, report.duplicates), (0, 3));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn csv_import()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        // Toggl: end date/time; a description w/ the delimiter and a quote
+        let toggl = "\u{feff}User,Project,Description,Start date,Start time,\
+                     End date,End time,Duration,Tags\n\
+                     me,Website relaunch,\"Header, \"\"v2\"\"\",\
+                     2024-01-10,08:00:00,2024-01-10,10:30:00,02:30:00,\
+                     \"design, review\"\n\
+                     me,Internal,,2024-01-10,13:00:00,2024-01-10,14:00:00,\
+                     01:00:00,\n\
+                     me,Private,,2024-01-10,18:00:00,2024-01-10,19:00:00,\
+                     01:00:00,\n\
+                     me,Internal,,2024-01-11,xx,2024-01-11,09:00:00,,\n";
+
+        let rules = parse_rules("Website*=B; Private=-").unwrap();
+        assert_eq!(map_project(&rules, "Website"), Some(Some("B".into())));
+        assert_eq!(map_project(&rules, "Private"), Some(None));
+        assert_eq!(map_project(&rules, "Internal"), None);
+        assert!(parse_rules("Website").is_err());
+        assert!(matches_pattern("*site*", "Website relaunch"));
+        assert!(!matches_pattern("Web*x", "Website"));
+
+        let mut asked = Vec::new();
+        let mut mapping = |project : &str| {
+            asked.push(project.to_string());
+            Ok(map_project(&rules, project)
+               .unwrap_or_else(|| Some(project.to_string())))
+        };
+
+        let report = csv(&db, &mut toggl.as_bytes(), &mut mapping).unwrap();
+        assert_eq!(asked, vec!["Website relaunch", "Internal", "Private"]);
+        assert_eq!((report.activities, report.intervals, report.duplicates,
+                    report.left_out), (1, 2, 0, 1));
+        assert_eq!(report.skipped, vec!["row 5: Invalid time: xx"]);
+
+        let day = NaiveDate::from_ymd_opt(2024, 01, 10).unwrap();
+        let sessions = sessions::get(&db, day, day).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].id, 2);
+        assert!((sessions[0].hours() - 2.5).abs() <= epsilon);
+        assert_eq!(sessions[0].note.as_deref(), Some("Header, \"v2\""));
+        assert_eq!(crate::db::tags::of_session(&db, sessions[0].sid).unwrap(),
+                   vec!["design", "review"]);
+        assert!((crate::db::hours_on_day(&db, 2, day) - 2.5).abs()
+                <= epsilon);
+
+        // Clockify: semicolons, US dates, AM/PM, decimal duration only
+        let clockify = "Project;Description;Start Date;Start Time;\
+                        Duration (decimal)\r\n\
+                        Internal;;01/12/2024;01:00 PM;1.5\r\n";
+        let report = csv(&db, &mut clockify.as_bytes(),
+                         &mut |p| Ok(Some(p.to_string()))).unwrap();
+        assert_eq!((report.intervals, report.skipped.len()), (1, 0));
+        let clockify = clockify.replace("(decimal)", "(h)")
+            .replace("1.5", "01:30:00");
+        let report = csv(&db, &mut clockify.as_bytes(),
+                         &mut |p| Ok(Some(p.to_string()))).unwrap();
+        assert_eq!((report.intervals, report.duplicates), (0, 1));
+
+        let internal = find_by_name(&db, "Internal").unwrap().unwrap();
+        assert!((total(&db) - 5.).abs() <= epsilon);
+        let day = NaiveDate::from_ymd_opt(2024, 01, 12).unwrap();
+        assert!((crate::db::hours_on_day(&db, internal, day) - 1.5).abs()
+                <= epsilon);
+
+        // importing again leaves everything as it was
+        let report = csv(&db, &mut toggl.as_bytes(),
+                         &mut |p| Ok(map_project(&rules, p)
+                                     .unwrap_or_else(|| Some(p.to_string()))))
+            .unwrap();
+        assert_eq!((report.intervals, report.duplicates), (0, 2));
+        assert!((total(&db) - 5.).abs() <= epsilon);
+
+        assert!(csv(&db, &mut "Project,Start date\n".as_bytes(),
+                    &mut |_| Ok(None)).is_err());
+    }
+
+    #[test]
+    fn json_format_1()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        let epsilon = 0.001;
+
+        let document = r#"{
+            "format": 1,
+            "exported": "2024-03-05T10:00:00+01:00",
+            "activities": [
+                { "name": "A", "added": "2020-01-01", "hourstotal": 3.5,
+                  "active": true, "archived": null },
+                { "name": "B", "added": "2020-01-01", "hourstotal": 2.0,
+                  "active": false, "archived": "2024-03-01" }
+            ],
+            "history": [
+                { "activity": "A", "date": "2024-01-02", "hours": 1.5 },
+                { "activity": "A", "date": "2024-01-03", "hours": 2.0 },
+                { "activity": "B", "date": "2024-01-02", "hours": 2.0 }
+            ]
+        }"#;
+
+        let report = json(&db, &mut document.as_bytes(), Duplicates::Skip)
+            .unwrap();
+        assert_eq!(report, Report { activities: 2, entries: 3,
+                                    summed: 0, skipped: 0 });
+        assert!((total(&db) - 5.5).abs() <= epsilon);
+        assert_eq!(crate::db::get_activities(&db, false).unwrap().len(), 1);
+
+        // day totals w/o times of day: manual sessions, which don't pile up
+        // in the first hours of the day
+        let day = NaiveDate::from_ymd_opt(2024, 01, 02).unwrap();
+        let sessions = sessions::get(&db, day, day).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().all(|s| s.source == Source::Manual));
+        let buckets = crate::db::stat::helpers::retrieve_hours_of_day(
+            &db, &[1, 2], day, day).unwrap();
+        assert!(buckets.iter().flatten().all(|hours| *hours == 0.));
+
+        let report = json(&db, &mut document.as_bytes(), Duplicates::Sum)
+            .unwrap();
+        assert_eq!((report.activities, report.summed), (0, 3));
+        assert!((total(&db) - 11.).abs() <= epsilon);
+    }
+
+    #[test]
+    fn json_roundtrip()
+    {
+        use crate::db::{goals, projects, settings, tags};
+        use chrono::TimeZone;
+
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        let at = |d, h, m| Local.with_ymd_and_hms(2024, 01, d, h, m, 0)
+            .unwrap();
+        let enter = |beg, end, id, kind, source, note : Option<&str>, tag| {
+            let sid = sessions::enter(&db, &beg, &end, id, kind, source)
+                .unwrap();
+            sessions::set_note(&db, sid, note).unwrap();
+            tags::add(&db, sid, &tags::parse(tag)).unwrap();
+        };
+        enter(at(10, 9, 0), at(10, 11, 0), 1, Kind::Work, Source::Timer,
+              Some("standup"), "meeting");
+        enter(at(10, 11, 0), at(10, 11, 30), 1, Kind::Break, Source::Timer,
+              None, "");
+        enter(at(10, 22, 0), at(11, 1, 0), 2, Kind::Work, Source::Import,
+              None, "review deep-work");
+        enter(at(12, 0, 0), at(12, 1, 30), 3, Kind::Work, Source::Manual,
+              None, "");
+
+        let acme = projects::add_client(&db, "ACME").unwrap();
+        let web = projects::add_project(&db, "Website", Some(acme)).unwrap();
+        let own = projects::add_project(&db, "Internal", None).unwrap();
+        projects::assign(&db, 1, Some(web)).unwrap();
+        projects::assign(&db, 3, Some(own)).unwrap();
+        goals::set(&db, None, goals::Period::Week, 40.).unwrap();
+        goals::set(&db, Some(1), goals::Period::Day, 4.).unwrap();
+        settings::set(&db, settings::KEY_STREAK_HOURS, "2").unwrap();
+        crate::db::deactivate_activity(&db, 4).unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(export::json(&db, &mut out).unwrap(), (4, 4));
+
+        // restore into an empty db
+        let mut copy = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut copy);
+        let report = json(&copy, &mut out.as_slice(), Duplicates::Skip)
+            .unwrap();
+        assert_eq!(report, Report { activities: 4, entries: 3,
+                                    summed: 0, skipped: 0 });
+        assert!((total(&copy) - 6.5).abs() <= epsilon);
+        assert!(crate::db::verify(&copy).unwrap().is_empty());
+
+        let deactivated = crate::db::get_activities(&copy, false).unwrap();
+        assert_eq!(deactivated.len(), 1);
+        assert_eq!(deactivated[0].name, "D");
+
+        let day = NaiveDate::from_ymd_opt(2024, 01, 11).unwrap();
+        assert!((crate::db::hours_on_day(&copy, 2, day) - 1.).abs()
+                <= epsilon);
+
+        // sessions keep their times, kind, source, note and tags
+        let day = NaiveDate::from_ymd_opt(2024, 01, 10).unwrap();
+        let restored = sessions::get(&copy, day, day).unwrap();
+        let original = sessions::get(&db, day, day).unwrap();
+        assert_eq!(restored.len(), 3);
+        for (r, o) in restored.iter().zip(&original)
+        {
+            assert_eq!((r.id, r.started, r.ended, r.kind, r.source, &r.note),
+                       (o.id, o.started, o.ended, o.kind, o.source, &o.note));
+            assert_eq!(tags::of_session(&copy, r.sid).unwrap(),
+                       tags::of_session(&db, o.sid).unwrap());
+        }
+
+        // so do projects, clients, goals and settings
+        let names = |db : &Connection| {
+            crate::db::get_all_activities(db).unwrap().iter()
+                .map(|a| (a.name.clone(), a.project))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&copy), names(&db));
+        let project = &projects::get_projects(&copy).unwrap()[1];
+        assert_eq!((project.name.as_str(), project.client.is_some()),
+                   ("Website", true));
+        assert_eq!(goals::get(&copy).unwrap(), goals::get(&db).unwrap());
+        assert!((settings::streak_hours(&copy).unwrap() - 2.).abs()
+                <= epsilon);
+
+        // merge into a db w/ the same data
+        let report = json(&copy, &mut out.as_slice(), Duplicates::Skip)
+            .unwrap();
+        assert_eq!(report.skipped, 3);
+        assert!((total(&copy) - 6.5).abs() <= epsilon);
+
+        let report = json(&copy, &mut out.as_slice(), Duplicates::Sum)
+            .unwrap();
+        assert_eq!((report.activities, report.summed), (0, 3));
+        assert!((total(&copy) - 13.).abs() <= epsilon);
+
+        // invalid documents change nothing
+        let broken = String::from_utf8(out).unwrap()
+            .replacen("\"activity\": \"C\"", "\"activity\": \"Z\"", 1);
+        assert!(json(&copy, &mut broken.as_bytes(), Duplicates::Sum)
+                .is_err());
+        assert!((total(&copy) - 13.).abs() <= epsilon);
+        assert!(json(&copy, &mut "{}".as_bytes(), Duplicates::Sum).is_err());
+        let newer = r#"{ "format": 3, "exported": "", "activities": [] }"#;
+        assert!(json(&copy, &mut newer.as_bytes(), Duplicates::Sum).is_err());
+    }
+}
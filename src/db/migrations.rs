@@ -0,0 +1,307 @@
+//! versioned schema migrations; the schema version of a db is kept in
+//! `PRAGMA user_version`, version 0 being the original layout of the
+//! activities and history table (queries::SQL_CREATE_ACT/HIS)
+//!
+//! to change the schema: append a function to MIGRATIONS, never alter or
+//! reorder existing ones (dbs out there have already applied them)
+
+use std::error;
+use std::path::{Path, PathBuf};
+use chrono::{Duration, NaiveDate};
+use rusqlite::{Connection, params};
+use super::queries::*;
+use super::sessions::{self, Kind, Source};
+
+type Migration = fn(&Connection) -> Result<(), Box<dyn error::Error>>;
+
+/// ordered migrations; applying MIGRATIONS[i] upgrades to version i + 1
+const MIGRATIONS : &[Migration] = &[
+    v1_timer,
+    v2_sessions,
+    v3_activity_status,
+    v4_projects,
+    v5_tags,
+    v6_notes,
+    v7_settings,
+    v8_goals,
+];
+
+/// schema version of a db w/ all migrations applied
+pub const LATEST : i32 = MIGRATIONS.len() as i32;
+
+/// retrieve schema version of db
+pub fn version(db : &Connection) -> rusqlite::Result<i32>
+{
+    db.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// apply all pending migrations within one transaction; if `backup` is given
+/// a copy of the db is written there beforehand (only if there's anything to
+/// migrate); returns the version the db had before
+pub fn migrate(db : &Connection, backup : Option<&Path>)
+    -> Result<i32, Box<dyn error::Error>>
+{
+    let from = version(db)?;
+
+    if from > LATEST
+    {
+        return Err(format!(
+                "DB schema version {} is newer than this application's ({})",
+                from, LATEST).into());
+    }
+    if from == LATEST { return Ok(from); }
+
+    if let Some(path) = backup
+    {
+        db.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+    }
+
+    let tx = db.unchecked_transaction()?;
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(from as usize)
+    {
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", index as i32 + 1)?;
+    }
+
+    tx.commit()?;
+
+    Ok(from)
+}
+
+/// path of the backup written before migrating a db file from `version`
+pub fn backup_path(dbpath : &Path, version : i32) -> PathBuf
+{
+    let mut name = dbpath.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}-{}.bak",
+                      version,
+                      chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    dbpath.with_file_name(name)
+}
+
+/// persisted running timer
+fn v1_timer(db : &Connection) -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(SQL_CREATE_TMR, ())?;
+    Ok(())
+}
+
+/// sessions table; existing history rows are carried over as `Legacy`
+/// sessions (beginning at midnight, same as manual entries)
+fn v2_sessions(db : &Connection) -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(SQL_CREATE_SES, ())?;
+
+    let mut stmt = db.prepare(
+        &format!("SELECT id, date, hoursonday FROM {} ORDER BY date ASC",
+                 SQL_TABLEN_HIS))?;
+
+    let legacy = stmt.query_map([], |row| {
+        Ok((row.get::<_, i32>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, f64>(2)?))
+    })?;
+
+    for row in legacy
+    {
+        let (id, date, hours) = row?;
+        if hours <= 0. { continue; }
+
+        let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")?;
+        let started = super::helpers::local_midnight(date)?;
+        let ended =
+            started + Duration::seconds((hours * 3600.).round() as i64);
+
+        db.execute(
+            &format!("INSERT INTO {} (id, started, ended, kind, source)
+                     VALUES (?1, ?2, ?3, ?4, ?5)", SQL_TABLEN_SES),
+            params![id,
+                    sessions::to_utc_string(&started),
+                    sessions::to_utc_string(&ended),
+                    Kind::Work.as_str(),
+                    Source::Legacy.as_str()])?;
+    }
+
+    Ok(())
+}
+
+/// activities keep their id for good and carry an active flag (and the date
+/// of their deactivation) instead; deactivation used to move activities to
+/// negative ids, those get the next free positive ids
+fn v3_activity_status(db : &Connection) -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(
+        &format!("ALTER TABLE {} ADD COLUMN active INTEGER NOT NULL DEFAULT 1",
+                 SQL_TABLEN_ACT), ())?;
+    db.execute(
+        &format!("ALTER TABLE {} ADD COLUMN archived TEXT", SQL_TABLEN_ACT),
+        ())?;
+
+    // foreign keys are only checked once the transaction commits
+    db.execute_batch("PRAGMA defer_foreign_keys = ON;")?;
+
+    let mut stmt = db.prepare(
+        &format!("SELECT id FROM {} WHERE id < 0 ORDER BY id DESC",
+                 SQL_TABLEN_ACT))?;
+    let inactive : Vec<i32> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    for oldid in inactive
+    {
+        let newid : i32 = db.query_row(
+            &format!("SELECT COALESCE(MAX(id), 0) + 1 FROM {}",
+                     SQL_TABLEN_ACT),
+            [], |row| row.get(0))?;
+
+        db.execute(
+            &format!("UPDATE {} SET id = ?1, active = 0 WHERE id = ?2",
+                     SQL_TABLEN_ACT),
+            params![newid, oldid])?;
+
+        for table in [SQL_TABLEN_HIS, SQL_TABLEN_SES, SQL_TABLEN_TMR]
+        {
+            db.execute(
+                &format!("UPDATE {} SET id = ?1 WHERE id = ?2", table),
+                params![newid, oldid])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// clients and projects grouping activities (tt_activities.project)
+fn v4_projects(db : &Connection) -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(SQL_CREATE_CLI, ())?;
+    db.execute(SQL_CREATE_PRJ, ())?;
+    db.execute(
+        &format!("ALTER TABLE {} ADD COLUMN project INTEGER
+                 REFERENCES {}(id)", SQL_TABLEN_ACT, SQL_TABLEN_PRJ), ())?;
+
+    Ok(())
+}
+
+/// tags on sessions; a running timer keeps the tags for the sessions it'll
+/// record (comma separated)
+fn v5_tags(db : &Connection) -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(SQL_CREATE_TAG, ())?;
+    db.execute(SQL_CREATE_STG, ())?;
+    db.execute(
+        &format!("ALTER TABLE {} ADD COLUMN tags TEXT NOT NULL DEFAULT ''",
+                 SQL_TABLEN_TMR), ())?;
+
+    Ok(())
+}
+
+/// optional note on what was done during a session; a running timer keeps
+/// the note for the sessions it'll record
+fn v6_notes(db : &Connection) -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(
+        &format!("ALTER TABLE {} ADD COLUMN note TEXT", SQL_TABLEN_SES), ())?;
+    db.execute(
+        &format!("ALTER TABLE {} ADD COLUMN note TEXT", SQL_TABLEN_TMR), ())?;
+
+    Ok(())
+}
+
+fn v7_settings(db : &Connection) -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(SQL_CREATE_SET, ())?;
+
+    Ok(())
+}
+
+fn v8_goals(db : &Connection) -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(SQL_CREATE_GOL, ())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn migrate_from_version_0()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+
+        // db as created before migrations existed, w/ some data
+        db.execute(SQL_CREATE_ACT, ()).unwrap();
+        db.execute(SQL_CREATE_HIS, ()).unwrap();
+        test::populate_db_w_activities(&mut db);
+        test::populate_db_w_data(&mut db);
+        assert_eq!(version(&db).unwrap(), 0);
+
+        assert_eq!(migrate(&db, None).unwrap(), 0);
+        assert_eq!(version(&db).unwrap(), LATEST);
+        assert!(super::super::verify(&db).unwrap().is_empty());
+
+        // history carried over as legacy sessions
+        let day = NaiveDate::from_ymd_opt(2024, 01, 02).unwrap();
+        let sessions = sessions::get(&db, day, day).unwrap();
+        assert_eq!(sessions.len(), 4);
+        assert!(sessions.iter().all(|s| s.source == Source::Legacy));
+        assert!((sessions[0].hours() - 1.5).abs() <= 0.001);
+
+        // nothing left to do
+        assert_eq!(migrate(&db, None).unwrap(), LATEST);
+    }
+
+    #[test]
+    fn negative_ids_rewritten()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+
+        // activity B deactivated the old way (moved to -1, C and D shifted)
+        db.execute(SQL_CREATE_ACT, ()).unwrap();
+        db.execute(SQL_CREATE_HIS, ()).unwrap();
+        test::populate_db_w_activities(&mut db);
+        test::populate_db_w_data(&mut db);
+        db.execute_batch("
+            PRAGMA foreign_keys = OFF;
+            UPDATE tt_activities SET id = -1 WHERE id = 2;
+            UPDATE tt_history    SET id = -1 WHERE id = 2;
+            UPDATE tt_activities SET id = 2  WHERE id = 3;
+            UPDATE tt_history    SET id = 2  WHERE id = 3;
+            UPDATE tt_activities SET id = 3  WHERE id = 4;
+            UPDATE tt_history    SET id = 3  WHERE id = 4;
+            PRAGMA foreign_keys = ON;").unwrap();
+
+        migrate(&db, None).unwrap();
+
+        let active = super::super::get_activities(&db, true).unwrap();
+        let inactive = super::super::get_activities(&db, false).unwrap();
+        assert_eq!(active.len(), 3);
+        assert_eq!(inactive.len(), 1);
+        assert_eq!((inactive[0].id, inactive[0].name.as_str()), (4, "B"));
+
+        // history and (legacy) sessions followed
+        let negative : i32 = db.query_row(
+            "SELECT (SELECT COUNT(*) FROM tt_history WHERE id < 0) +
+                    (SELECT COUNT(*) FROM tt_sessions WHERE id < 0)",
+            [], |row| row.get(0)).unwrap();
+        assert_eq!(negative, 0);
+
+        let hours : f64 = db.query_row(
+            "SELECT SUM(hoursonday) FROM tt_history WHERE id = 4",
+            [], |row| row.get(0)).unwrap();
+        assert!((hours - 562. / 4.).abs() <= 0.001);
+    }
+
+    #[test]
+    fn newer_version_rejected()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+
+        db.pragma_update(None, "user_version", LATEST + 1).unwrap();
+        assert!(migrate(&db, None).is_err());
+    }
+}
@@ -2,12 +2,21 @@
 //! (initialization, integrity checking, ...)
 //! stat functionality ousted to submodule stat
 
+pub mod export;
+pub mod goals;
 pub mod helpers;
+pub mod import;
+pub mod migrations;
+pub mod projects;
 pub mod queries;
+pub mod sessions;
+pub mod settings;
 pub mod stat;
+pub mod tags;
+pub mod timer;
 
 use std::error;
-use chrono::{DateTime, Datelike, Duration, Local};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
 use rusqlite::params;
 use rusqlite::Connection;
 use rusqlite::Result;
@@ -15,11 +24,13 @@ use queries::*;
 use helpers::*;
 
 /// representing a row from Activities table;
-/// only id, name, is_activated
+/// only id, name, is_activated, project (see projects.rs)
 #[derive(Debug, Clone)]
 pub struct ActivitiesRow {
     pub id: i32,
     pub name: String,
+    pub active: bool,
+    pub project: Option<i32>,
 }
 
 /// representing a row from History table;
@@ -40,11 +51,20 @@ pub struct StatWeeklyRow {
     pub hours: f64,
 }
 
-/// initialize a newly created db and populate with user data
-/// after table creation hands user off to crate::conf() (see lib.rs)
-pub fn init(db: &mut Connection) -> Result<()> {
+/// create all tables of a newly created db (original layout plus all
+/// migrations, see migrations.rs)
+pub fn create_tables(db: &Connection) -> Result<(), Box<dyn error::Error>> {
     db.execute(SQL_CREATE_ACT, ())?;
     db.execute(SQL_CREATE_HIS, ())?;
+    migrations::migrate(db, None)?;
+
+    Ok(())
+}
+
+/// initialize a newly created db and populate with user data
+/// after table creation hands user off to crate::conf() (see lib.rs)
+pub fn init(db: &mut Connection) -> Result<(), Box<dyn error::Error>> {
+    create_tables(db)?;
 
     // initialization complete
     // send user off to configure the db (add activities and such)
@@ -53,75 +73,115 @@ pub fn init(db: &mut Connection) -> Result<()> {
     Ok(())
 }
 
-/// check existing db for integrity, conforming to expected layout
-pub fn check(db : &Connection) -> Result<()> {
-    // more extensive check
-    // compare creation schema versus one from sqlite_master
+/// compare db against the expected layout (a freshly created in memory db);
+/// every expected table and column has to exist, additional ones are fine;
+/// returns a description of every deviation (empty if db conforms)
+pub fn verify(db : &Connection) -> Result<Vec<String>, Box<dyn error::Error>> {
+    let mut failed = Vec::new();
 
-    let mut letspanic = false;
+    let expected = Connection::open_in_memory()?;
+    create_tables(&expected)?;
 
-    let mut stmt = db.prepare(
-        "SELECT sql FROM sqlite_master WHERE type='table' AND name=?1",
-    )?;
+    if migrations::version(db)? != migrations::LATEST {
+        failed.push(format!("schema version {} instead of {}",
+                            migrations::version(db)?, migrations::LATEST));
+    }
 
-    let schema_act: String =
-        stmt.query_row(params![SQL_TABLEN_ACT], |row| row.get(0))?;
-    let schema_his: String =
-        stmt.query_row(params![SQL_TABLEN_HIS], |row| row.get(0))?;
+    let mut stmt = expected.prepare(
+        "SELECT name FROM sqlite_master WHERE type='table' ORDER BY name")?;
+    let tables: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_>>()?;
 
-    println!(
-        "Checking if needed tables exist and are d'accord w/ creation queries"
-    );
+    for table in tables {
+        let present = table_columns(db, &table)?;
+
+        if present.is_empty() {
+            failed.push(format!("table {} missing", table));
+            continue;
+        }
 
-    if !(clean(schema_act) == clean(SQL_CREATE_ACT.to_string())) {
-        println!("table {} failed integrity check", SQL_TABLEN_ACT);
-        letspanic = true;
+        for column in table_columns(&expected, &table)? {
+            if !present.contains(&column) {
+                failed.push(format!("column {}.{} missing", table, column));
+            }
+        }
     }
-    if !(clean(schema_his) == clean(SQL_CREATE_HIS.to_string())) {
-        println!("table {} failed integrity check", SQL_TABLEN_HIS);
-        letspanic = true;
+
+    Ok(failed)
+}
+
+fn table_columns(db: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = db.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let columns = stmt
+        .query_map(params![table], |row| row.get(0))?
+        .collect::<Result<_>>()?;
+
+    Ok(columns)
+}
+
+/// bring an existing db up to date and check it for integrity; migrating a
+/// db file (`dbpath`) writes a backup of it next to it beforehand
+pub fn upgrade(db : &Connection, dbpath : Option<&std::path::Path>)
+    -> Result<Option<(i32, std::path::PathBuf)>, Box<dyn error::Error>> {
+    let from = migrations::version(db)?;
+    let mut migrated = None;
+
+    if from < migrations::LATEST {
+        let backup = dbpath.map(|path| migrations::backup_path(path, from));
+        migrations::migrate(db, backup.as_deref())?;
+        migrated = backup.map(|backup| (from, backup));
     }
 
-    if !letspanic {
-        println!("  Passed");
-    } else {
-        panic!("DB tables failed integrity check, something's off");
+    let failed = verify(db)?;
+
+    if !failed.is_empty() {
+        return Err(format!("DB failed integrity check: {}",
+                           failed.join(", ")).into());
+    }
+
+    Ok(migrated)
+}
+
+/// check existing db for integrity, conforming to expected layout; older
+/// schema versions are migrated (see upgrade())
+pub fn check(db : &Connection, dbpath : Option<&std::path::Path>)
+    -> Result<(), Box<dyn error::Error>> {
+    println!(
+        "Checking if needed tables exist and are d'accord w/ expected layout"
+    );
+
+    if let Some((from, backup)) = upgrade(db, dbpath)? {
+        println!("  Migrated db from schema version {} to {}",
+                 from, migrations::LATEST);
+        println!("  Backup of previous db: {:?}", backup);
     }
 
+    println!("  Passed");
+
     Ok(())
 }
 
 /// retrieve activities from activities table;
 /// uses ActivitiesRow struct
 pub fn get_activities(
-    db			: &mut Connection,
+    db			: &Connection,
     activated 	: bool,
 ) -> Result<Vec<ActivitiesRow>, Box<dyn error::Error>> {
 
-	let mut stmt : rusqlite::Statement;
-
-    if activated
-    {
-        stmt = db.prepare(
-            &format!("SELECT id, name FROM {} 
-                     WHERE id > 0 ORDER BY id ASC",
-                     SQL_TABLEN_ACT)
-            )?;
-    }
-    else
-    {
-        stmt = db.prepare(
-            &format!("SELECT id, name FROM {}
-                     WHERE id < 0 ORDER BY id DESC",
-                     SQL_TABLEN_ACT)
-            )?;
-    }
+    let mut stmt = db.prepare(
+        &format!("SELECT id, name, active, project FROM {}
+                 WHERE active = ?1 ORDER BY id ASC",
+                 SQL_TABLEN_ACT)
+        )?;
 
     // create iterator
-    let db_activities_data = stmt.query_map([], |row| {
+    let db_activities_data = stmt.query_map(params![activated], |row| {
         Ok(ActivitiesRow {
             id: row.get(0)?,
             name: row.get(1)?,
+            active: row.get(2)?,
+            project: row.get(3)?,
         })
     })?;
 
@@ -134,6 +194,16 @@ pub fn get_activities(
     Ok(activities)
 }
 
+/// retrieve active and deactivated activities alike (active ones first)
+pub fn get_all_activities(
+    db: &Connection,
+) -> Result<Vec<ActivitiesRow>, Box<dyn error::Error>> {
+    let mut activities = get_activities(db, true)?;
+    activities.append(&mut get_activities(db, false)?);
+
+    Ok(activities)
+}
+
 /// given a db and activity id retrieves the activitie's name
 pub fn get_activityname_for_id(
     db: &Connection,
@@ -154,11 +224,223 @@ pub fn get_activityname_for_id(
     Ok(name)
 }
 
+/// given a db and user input retrieves the id of an activity; input can
+/// either be an id or the exact name of an activity
+pub fn find_activity(
+    db: &Connection,
+    input: &str,
+    activated: bool,
+) -> Result<i32, Box<dyn error::Error>> {
+    let activities = get_activities(db, activated)?;
+
+    if let Ok(id) = input.parse::<i32>() {
+        if activities.iter().any(|act| act.id == id) {
+            return Ok(id);
+        }
+    }
+
+    match activities.iter().find(|act| act.name == input) {
+        Some(act) => Ok(act.id),
+        None => Err(format!("No such activity: {}", input).into()),
+    }
+}
+
+/// add a new activity to the activities table; names have to be non-empty
+/// and unique (as lookups by name rely on)
+pub fn add_activity(
+    db: &Connection,
+    name: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    let name = projects::check_name(db, SQL_TABLEN_ACT, name)?;
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+
+    db.execute(
+        &format!("INSERT INTO {}
+                (name, added, hourstotal) 
+                VALUES
+                (?1, ?2, ?3)",
+                SQL_TABLEN_ACT),
+                params![name, date, 0.]
+              )?;
+
+    Ok(())
+}
+
+/// deactivate (archive) an activity; it keeps its id and all its data, but
+/// is no longer offered for tracking and per activity statistics
+pub fn deactivate_activity(
+    db: &Connection,
+    id: i32,
+) -> Result<(), Box<dyn error::Error>> {
+    if timer::get(db)?.is_some_and(|timer| timer.id == id) {
+        return Err("Stop the running timer before deactivating".into());
+    }
+
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+
+    let changed = db.execute(
+        &format!("UPDATE {} SET active = 0, archived = ?1
+                 WHERE id = ?2 AND active = 1",
+                 SQL_TABLEN_ACT),
+        params![date, id])?;
+
+    if changed == 0 {
+        return Err("No such active activity".into());
+    }
+
+    Ok(())
+}
+
+/// reactivate a deactivated activity
+pub fn reactivate_activity(
+    db: &Connection,
+    id: i32,
+) -> Result<(), Box<dyn error::Error>> {
+    let changed = db.execute(
+        &format!("UPDATE {} SET active = 1, archived = NULL
+                 WHERE id = ?1 AND active = 0",
+                 SQL_TABLEN_ACT),
+        params![id])?;
+
+    if changed == 0 {
+        return Err("No such deactivated activity".into());
+    }
+
+    Ok(())
+}
+
+/// rename an activity; names have to be non-empty and unique
+pub fn rename_activity(
+    db: &Connection,
+    id: i32,
+    name: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err("Activity name can't be empty".into());
+    }
+
+    let taken: i32 = db.query_row(
+        &format!("SELECT COUNT(*) FROM {} WHERE name = ?1 AND id != ?2",
+                 SQL_TABLEN_ACT),
+        params![name, id],
+        |row| row.get(0))?;
+
+    if taken > 0 {
+        return Err(format!("Activity {} already exists", name).into());
+    }
+
+    let changed = db.execute(
+        &format!("UPDATE {} SET name = ?1 WHERE id = ?2", SQL_TABLEN_ACT),
+        params![name, id])?;
+
+    if changed == 0 {
+        return Err("No such activity in db".into());
+    }
+
+    Ok(())
+}
+
+/// number of history rows (days w/ entries) of an activity
+pub fn count_history_rows(db: &Connection, id: i32) -> Result<i64> {
+    db.query_row(
+        &format!("SELECT COUNT(*) FROM {} WHERE id = ?1", SQL_TABLEN_HIS),
+        params![id],
+        |row| row.get(0))
+}
+
+/// merge activity `from` into activity `into`; history rows of both on the
+/// same day are summed up, as are their total hours; sessions are moved
+/// along and `from` is removed; returns the number of history rows moved
+pub fn merge_activities(
+    db: &Connection,
+    from: i32,
+    into: i32,
+) -> Result<i64, Box<dyn error::Error>> {
+    if from == into {
+        return Err("Can't merge an activity into itself".into());
+    }
+
+    get_activityname_for_id(db, from)?;
+    get_activityname_for_id(db, into)?;
+
+    if timer::get(db)?.is_some_and(|timer| timer.id == from) {
+        return Err("Stop the running timer before merging".into());
+    }
+
+    let moved = count_history_rows(db, from)?;
+    let tx = db.unchecked_transaction()?;
+
+    // days on which both have an entry: sum up into `into`
+    tx.execute(
+        &format!("UPDATE {his} SET hoursonday = ROUND(hoursonday +
+                     (SELECT other.hoursonday FROM {his} AS other
+                      WHERE other.id = ?1 AND other.date = {his}.date), 6)
+                 WHERE id = ?2 AND date IN
+                     (SELECT date FROM {his} WHERE id = ?1)",
+                 his = SQL_TABLEN_HIS),
+        params![from, into])?;
+    tx.execute(
+        &format!("DELETE FROM {his} WHERE id = ?1 AND date IN
+                     (SELECT date FROM {his} WHERE id = ?2)",
+                 his = SQL_TABLEN_HIS),
+        params![from, into])?;
+
+    // all other days simply change owner
+    for table in [SQL_TABLEN_HIS, SQL_TABLEN_SES] {
+        tx.execute(&format!("UPDATE {} SET id = ?1 WHERE id = ?2", table),
+                   params![into, from])?;
+    }
+
+    tx.execute(
+        &format!("UPDATE {act} SET hourstotal = ROUND(hourstotal +
+                     (SELECT hourstotal FROM {act} WHERE id = ?1), 6)
+                 WHERE id = ?2",
+                 act = SQL_TABLEN_ACT),
+        params![from, into])?;
+    tx.execute(&format!("DELETE FROM {} WHERE id = ?1", SQL_TABLEN_ACT),
+               params![from])?;
+    goals::remove_all_of(&tx, from)?;
+
+    tx.commit()?;
+
+    Ok(moved)
+}
+
+/// permanently delete an activity along w/ its history and sessions;
+/// returns the number of history rows deleted
+pub fn delete_activity(
+    db: &Connection,
+    id: i32,
+) -> Result<i64, Box<dyn error::Error>> {
+    get_activityname_for_id(db, id)?;
+
+    if timer::get(db)?.is_some_and(|timer| timer.id == id) {
+        return Err("Stop the running timer before deleting".into());
+    }
+
+    let tx = db.unchecked_transaction()?;
+
+    tx.execute(&format!("DELETE FROM {} WHERE id = ?1", SQL_TABLEN_SES),
+               params![id])?;
+    let deleted = tx.execute(
+        &format!("DELETE FROM {} WHERE id = ?1", SQL_TABLEN_HIS),
+        params![id])?;
+    tx.execute(&format!("DELETE FROM {} WHERE id = ?1", SQL_TABLEN_ACT),
+               params![id])?;
+    goals::remove_all_of(&tx, id)?;
+
+    tx.commit()?;
+
+    Ok(deleted as i64)
+}
+
 /// make an entry into the db; handles midnight turnover, localtime updates, 
 /// and invokes the entry functions for the stat tables; is also used for 
 /// manual db entries;
 pub fn enter_into_db(
-    db: &mut Connection,
+    db: &Connection,
     dtbeg: &DateTime<Local>,
     dtend: &DateTime<Local>,
     actid: i32,
@@ -183,38 +465,13 @@ pub fn enter_into_db(
     let datebeg = dtbeg.format("%Y-%m-%d").to_string();
     let dateend = dtend.format("%Y-%m-%d").to_string();
 
-    if dtbeg.date_naive() < dtend.date_naive() 
-    {
-        // important to test for `<` and not `!=` here
-        // in strict theory dtend.date_naive() can be before dtbeg.date_naive()
-        // (consider taking a flight slightly past midnight, flying eastward
-        // such that you land "on the previous day")
-        // in such a case we'll simply ignore the date change
+    // hours attributed to day of beginning (and day of end, if different)
+    let perday = hours_per_day(dtbeg, dtend);
 
+    if perday.len() == 2 {
         ddchanged = true;
-
-        // we work w/ date_naive()! here which ignores localtime updates
-
-        let nextmidnight = (dtbeg.naive_local() + Duration::days(1))
-            .date()
-            .and_hms_opt(0, 0, 0)
-            .unwrap();
-
-        let lastmidnight = dtend.date_naive().and_hms_opt(0, 0, 0).unwrap();
-
-        tillmidnight =
-            (nextmidnight - dtbeg.naive_local()).num_seconds() as f64 / 3600.;
-
-        frommidnight =
-            (dtend.naive_local() - lastmidnight).num_seconds() as f64 / 3600.;
-
-        // if their sum is greater than previously computed duration
-        // we adjust the end time to reflect the UTC offset change which occured
-
-        if tillmidnight + frommidnight > durationhours {
-            frommidnight =
-                frommidnight - (tillmidnight + frommidnight - durationhours)
-        }
+        tillmidnight = perday[0].1;
+        frommidnight = perday[1].1;
     }
 
     // retrieve hours on day for activity
@@ -322,6 +579,147 @@ pub fn enter_into_db(
     Ok(())
 }
 
+/// counterpart to enter_into_db; deducts the hours of a time span from the
+/// history (removing rows which drop to zero) and activities table
+pub fn deduct_from_db(
+    db: &Connection,
+    dtbeg: &DateTime<Local>,
+    dtend: &DateTime<Local>,
+    actid: i32,
+) -> Result<()> {
+    let duration = dtend.signed_duration_since(dtbeg);
+    let durationhours = duration.num_seconds() as f64 / 3600.;
+
+    for (date, hours) in hours_per_day(dtbeg, dtend) {
+        let date = date.format("%Y-%m-%d").to_string();
+
+        let hours_on_day: f64 = db
+            .query_row(
+                &format!(
+                    "SELECT hoursonday FROM {} WHERE date=?1 AND id=?2",
+                    SQL_TABLEN_HIS),
+                params![date, actid],
+                |row| row.get(0),
+            )
+            .unwrap_or(0.);
+
+        let remaining = round(hours_on_day - hours);
+
+        if remaining <= 0. {
+            db.execute(
+                &format!("DELETE FROM {} WHERE date=?1 AND id=?2",
+                         SQL_TABLEN_HIS),
+                params![date, actid],
+            )?;
+        } else {
+            db.execute(
+                &format!("UPDATE {} SET hoursonday=?1 WHERE date=?2 AND id=?3",
+                         SQL_TABLEN_HIS),
+                params![remaining, date, actid],
+            )?;
+        }
+    }
+
+    let total_hours: f64 = db
+        .query_row(
+            &format!("SELECT hourstotal FROM {} WHERE id=?1", SQL_TABLEN_ACT),
+            [actid],
+            |row| row.get(0),
+        )
+        .unwrap_or(0.);
+
+    db.execute(
+        &format!("UPDATE {} SET hourstotal=?1 WHERE id=?2", SQL_TABLEN_ACT),
+        params![round(total_hours - round(durationhours)).max(0.), actid],
+    )?;
+
+    Ok(())
+}
+
+/// hours of an activity on a date (0 if there's no entry)
+pub fn hours_on_day(db: &Connection, actid: i32, date: NaiveDate) -> f64 {
+    db.query_row(
+        &format!("SELECT hoursonday FROM {} WHERE id = ?1 AND date = ?2",
+                 SQL_TABLEN_HIS),
+        params![actid, date.format("%Y-%m-%d").to_string()],
+        |row| row.get(0))
+        .unwrap_or(0.)
+}
+
+/// change the hours of an activity's entry on a date to `hours`; additional
+/// time is entered as manual session right after the day's last session (of
+/// any activity; it may overlap others only if there's no room left after
+/// it, it then ends at midnight), less time is taken off the day's sessions
+/// (see sessions::trim_day)
+pub fn set_day_hours(
+    db: &Connection,
+    actid: i32,
+    date: NaiveDate,
+    hours: f64,
+) -> Result<(), Box<dyn error::Error>> {
+    get_activityname_for_id(db, actid)?;
+
+    if !(0. ..24.).contains(&hours) {
+        return Err("Hours on a day have to be between 0 and 24".into());
+    }
+
+    let current = hours_on_day(db, actid, date);
+
+    if hours > current {
+        let midnight = local_midnight(date)?;
+        let next = local_midnight(date + chrono::Duration::days(1))?;
+        let extra = chrono::Duration::seconds(
+            ((hours - current) * 3600.).round() as i64);
+
+        let last = sessions::get(db, date, date)?
+            .iter()
+            .map(|session| session.ended)
+            .fold(midnight, |last, ended| last.max(ended));
+        let dtbeg = if last + extra <= next { last } else { next - extra };
+        let dtend = dtbeg + extra;
+
+        sessions::enter(db, &dtbeg, &dtend, actid,
+                        sessions::Kind::Work, sessions::Source::Manual)?;
+    } else if hours < current {
+        sessions::trim_day(db, actid, date, current - hours)?;
+    }
+
+    Ok(())
+}
+
+/// move `hours` of an activity's entry on a date to another activity; the
+/// time is taken off the day's sessions (see sessions::trim_day) and entered
+/// for the other activity as it was (same times, source, note and tags)
+pub fn move_hours(
+    db: &Connection,
+    from: i32,
+    into: i32,
+    date: NaiveDate,
+    hours: f64,
+) -> Result<(), Box<dyn error::Error>> {
+    get_activityname_for_id(db, into)?;
+
+    if from == into {
+        return Err("Can't move hours to the same activity".into());
+    }
+    if hours <= 0. || hours > hours_on_day(db, from, date) {
+        return Err("Can only move up to the hours of the entry".into());
+    }
+
+    let tx = db.unchecked_transaction()?;
+
+    for (session, beg, end) in sessions::trim_day(&tx, from, date, hours)? {
+        let sid = sessions::enter(&tx, &beg, &end, into,
+                                  session.kind, session.source)?;
+        sessions::set_note(&tx, sid, session.note.as_deref())?;
+        tags::add(&tx, sid, &tags::of_session(&tx, session.sid)?)?;
+    }
+
+    tx.commit()?;
+
+    Ok(())
+}
+
 /// remove an entry fully from db (history and stats tables)
 pub fn remove_from_db(
     db: &mut Connection,
@@ -381,6 +779,13 @@ pub fn remove_from_db(
 
     let mut rowschanged;
 
+    // callers may already have opened a transaction
+    let tx = if db.is_autocommit() {
+        Some(db.unchecked_transaction()?)
+    } else {
+        None
+    };
+
     // remove from history table
 
     rowschanged = db
@@ -409,12 +814,32 @@ pub fn remove_from_db(
 
     assert!(rowschanged == 1);
 
+    // sessions making up the removed entry
+    sessions::clear_day(db, actid, date.date_naive())?;
+
+    if let Some(tx) = tx {
+        tx.commit()?;
+    }
+
     Ok(())
 }
 
 /// retrieve eight day history; used to prompt for data removal
 pub fn retrieve_8day_history(
     db: &mut Connection,
+) -> Result<Vec<HistoryRow>, Box<dyn error::Error>> {
+    let today = chrono::Local::now().date_naive();
+
+    retrieve_history(db, today - chrono::Duration::days(7), today, None)
+}
+
+/// retrieve history of dates from..=to (newest first), optionally only of one
+/// activity; used to search entries for editing
+pub fn retrieve_history(
+    db: &Connection,
+    from: NaiveDate,
+    to: NaiveDate,
+    actid: Option<i32>,
 ) -> Result<Vec<HistoryRow>, Box<dyn error::Error>> {
     /*
         #[derive(Debug, Clone)]
@@ -425,17 +850,19 @@ pub fn retrieve_8day_history(
         }
     */
 
-    let today = chrono::Local::now();
-    let past8 = today - chrono::Duration::days(8);
-
     let mut stmt = db.prepare(
-        &format!("SELECT * FROM {} WHERE date > ?1 ORDER BY date DESC",
+        &format!("SELECT * FROM {} WHERE date >= ?1 AND date <= ?2
+                 AND (?3 IS NULL OR id = ?3)
+                 ORDER BY date DESC, id ASC",
         SQL_TABLEN_HIS)
     )?;
 
     // create iterator
-    let db_history_data =
-        stmt.query_map(params![past8.format("%Y-%m-%d").to_string()], |row| {
+    let db_history_data = stmt.query_map(
+        params![from.format("%Y-%m-%d").to_string(),
+                to.format("%Y-%m-%d").to_string(),
+                actid],
+        |row| {
             Ok(HistoryRow {
                 id: row.get(0)?,
                 date: row.get(7)?,
@@ -459,7 +886,6 @@ mod tests
     use crate::test; // crate w/ shared test logic
     
     #[test]
-    #[should_panic(expected = "failed integrity check")]
     fn extra_table_column_integrity_check()
     {
         let mut testdb = Connection::open_in_memory()
@@ -477,8 +903,34 @@ mod tests
             )
             .unwrap_or_else(|_| panic!("Couldn't add table column"));
 
-        // integrity check should now panic
-        let _ = check(&testdb);
+        // additional columns are tolerated
+        assert!(verify(&testdb).unwrap().is_empty());
+        assert!(upgrade(&testdb, None).is_ok());
+    }
+
+    #[test]
+    fn missing_table_column_integrity_check()
+    {
+        let mut testdb = Connection::open_in_memory()
+            .expect("Failed to open");
+
+        test::initialize_db(&mut testdb);
+
+        testdb
+            .execute(
+                &format!(
+                    "ALTER TABLE {} DROP COLUMN isoweekyear",
+                    SQL_TABLEN_HIS),
+                (),
+            )
+            .unwrap_or_else(|_| panic!("Couldn't drop table column"));
+        testdb
+            .execute(&format!("DROP TABLE {}", SQL_TABLEN_TMR), ())
+            .unwrap_or_else(|_| panic!("Couldn't drop table"));
+
+        let failed = verify(&testdb).unwrap();
+        assert_eq!(failed.len(), 2);
+        assert!(upgrade(&testdb, None).is_err());
     }
 
     #[test]
@@ -496,6 +948,127 @@ mod tests
         assert_eq!(vec[3].name, "D");
     }
 
+    #[test]
+    fn deactivation_keeps_ids()
+    {
+        let mut testdb = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut testdb);
+        test::populate_db_w_activities(&mut testdb);
+
+        deactivate_activity(&testdb, 2).unwrap();
+        assert!(deactivate_activity(&testdb, 2).is_err());
+
+        let active = get_activities(&testdb, true).unwrap();
+        let inactive = get_activities(&testdb, false).unwrap();
+        assert_eq!(active.iter().map(|a| a.id).collect::<Vec<_>>(),
+                   vec![1, 3, 4]);
+        assert_eq!(inactive.len(), 1);
+        assert_eq!((inactive[0].id, inactive[0].name.as_str()), (2, "B"));
+        assert!(!inactive[0].active);
+
+        reactivate_activity(&testdb, 2).unwrap();
+        assert!(reactivate_activity(&testdb, 2).is_err());
+        assert_eq!(get_activities(&testdb, true).unwrap().len(), 4);
+        assert_eq!(get_activityname_for_id(&testdb, 2).unwrap(), "B");
+    }
+
+    #[test]
+    fn rename_merge_delete()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        test::populate_db_w_data(&mut db);
+        let epsilon = 0.001;
+
+        assert!(rename_activity(&db, 1, "B").is_err());
+        assert!(rename_activity(&db, 1, " ").is_err());
+        assert!(add_activity(&db, " B ").is_err());
+        assert!(add_activity(&db, "").is_err());
+        rename_activity(&db, 1, "Alpha").unwrap();
+        assert_eq!(get_activityname_for_id(&db, 1).unwrap(), "Alpha");
+
+        // every day w/ an entry of B also has one of A
+        let rows_a = count_history_rows(&db, 1).unwrap();
+        let rows_b = count_history_rows(&db, 2).unwrap();
+        assert!(merge_activities(&db, 2, 2).is_err());
+        assert_eq!(merge_activities(&db, 2, 1).unwrap(), rows_b);
+        assert_eq!(count_history_rows(&db, 1).unwrap(), rows_a);
+        assert_eq!(count_history_rows(&db, 2).unwrap(), 0);
+        assert!(get_activityname_for_id(&db, 2).is_err());
+
+        let hours_total : f64 = db.query_row(
+            &format!("SELECT hourstotal FROM {} WHERE id=1", SQL_TABLEN_ACT),
+            (), |row| row.get(0)).unwrap();
+        assert!((hours_total - 2. * 562. / 4.).abs() <= epsilon);
+
+        let hours_on_day : f64 = db.query_row(
+            &format!("SELECT hoursonday FROM {}
+                     WHERE id=1 AND date='2024-01-02'", SQL_TABLEN_HIS),
+            (), |row| row.get(0)).unwrap();
+        assert!((hours_on_day - 1.5 * 2.).abs() <= epsilon);
+
+        let rows_c = count_history_rows(&db, 3).unwrap();
+        assert_eq!(delete_activity(&db, 3).unwrap(), rows_c);
+        assert_eq!(count_history_rows(&db, 3).unwrap(), 0);
+        assert!(delete_activity(&db, 3).is_err());
+        assert_eq!(get_activities(&db, true).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn edit_day_hours()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        test::populate_db_w_data(&mut db);
+        let epsilon = 0.001;
+
+        let consistent = |db : &Connection| {
+            let diff : f64 = db.query_row(
+                &format!("SELECT SUM(ABS(a.hourstotal -
+                             (SELECT IFNULL(SUM(h.hoursonday), 0) FROM {} AS h
+                              WHERE h.id = a.id)))
+                         FROM {} AS a", SQL_TABLEN_HIS, SQL_TABLEN_ACT),
+                (), |row| row.get(0)).unwrap();
+            diff <= epsilon
+        };
+
+        let day = NaiveDate::from_ymd_opt(2024, 01, 02).unwrap();
+        assert!((hours_on_day(&db, 1, day) - 1.5).abs() <= epsilon);
+
+        set_day_hours(&db, 1, day, 4.).unwrap();
+        assert!((hours_on_day(&db, 1, day) - 4.).abs() <= epsilon);
+        set_day_hours(&db, 1, day, 3.).unwrap();
+        assert!((hours_on_day(&db, 1, day) - 3.).abs() <= epsilon);
+        assert!(set_day_hours(&db, 1, day, 24.).is_err());
+        assert!(consistent(&db));
+
+        // added hours go after the day's last session
+        let beg = local_midnight(day).unwrap() + chrono::Duration::hours(9);
+        sessions::enter(&db, &beg, &(beg + chrono::Duration::hours(1)), 2,
+                        sessions::Kind::Work, sessions::Source::Timer)
+            .unwrap();
+        set_day_hours(&db, 1, day, 3.5).unwrap();
+        let added = sessions::get(&db, day, day).unwrap().pop().unwrap();
+        assert_eq!(added.id, 1);
+        assert_eq!(added.started, beg + chrono::Duration::hours(1));
+        assert!((added.hours() - 0.5).abs() <= epsilon);
+        set_day_hours(&db, 1, day, 3.).unwrap();
+        assert!(consistent(&db));
+
+        let before = hours_on_day(&db, 2, day);
+        assert!(move_hours(&db, 1, 1, day, 1.).is_err());
+        assert!(move_hours(&db, 1, 2, day, 5.).is_err());
+        move_hours(&db, 1, 2, day, 1.).unwrap();
+        assert!((hours_on_day(&db, 1, day) - 2.).abs() <= epsilon);
+        assert!((hours_on_day(&db, 2, day) - before - 1.).abs() <= epsilon);
+        assert!(consistent(&db));
+
+        let rows = retrieve_history(&db, day, day, Some(1)).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert!((rows[0].hours - 2.).abs() <= epsilon);
+    }
     
     #[test]
     // uses populated in memory db and tests expectations on tables
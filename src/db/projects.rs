@@ -0,0 +1,251 @@
+//! grouping of activities: client -> project -> activity; an activity belongs
+//! to at most one project, a project to at most one client; statistics roll
+//! their totals up along this hierarchy (see db::stat)
+
+use std::error;
+use rusqlite::{Connection, params};
+use super::queries::*;
+
+/// representing a row from Clients table
+#[derive(Debug, Clone)]
+pub struct ClientRow {
+    pub id: i32,
+    pub name: String,
+}
+
+/// representing a row from Projects table
+#[derive(Debug, Clone)]
+pub struct ProjectRow {
+    pub id: i32,
+    pub name: String,
+    pub client: Option<i32>,
+}
+
+/// retrieve all clients, ordered by name
+pub fn get_clients(db : &Connection)
+    -> Result<Vec<ClientRow>, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(
+        &format!("SELECT id, name FROM {} ORDER BY name ASC", SQL_TABLEN_CLI))?;
+
+    let clients = stmt
+        .query_map([], |row| {
+            Ok(ClientRow { id: row.get(0)?, name: row.get(1)? })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    Ok(clients)
+}
+
+/// retrieve all projects, ordered by name
+pub fn get_projects(db : &Connection)
+    -> Result<Vec<ProjectRow>, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(
+        &format!("SELECT id, name, client FROM {} ORDER BY name ASC",
+                 SQL_TABLEN_PRJ))?;
+
+    let projects = stmt
+        .query_map([], |row| {
+            Ok(ProjectRow {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                client: row.get(2)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    Ok(projects)
+}
+
+/// add a client; returns its id
+pub fn add_client(db : &Connection, name : &str)
+    -> Result<i32, Box<dyn error::Error>>
+{
+    let name = check_name(db, SQL_TABLEN_CLI, name)?;
+
+    db.execute(
+        &format!("INSERT INTO {} (name, added) VALUES (?1, ?2)",
+                 SQL_TABLEN_CLI),
+        params![name, today()])?;
+
+    Ok(db.last_insert_rowid() as i32)
+}
+
+/// add a project, optionally belonging to a client; returns its id
+pub fn add_project(db : &Connection, name : &str, client : Option<i32>)
+    -> Result<i32, Box<dyn error::Error>>
+{
+    let name = check_name(db, SQL_TABLEN_PRJ, name)?;
+
+    if let Some(client) = client
+    {
+        exists(db, SQL_TABLEN_CLI, client).map_err(|_| "No such client")?;
+    }
+
+    db.execute(
+        &format!("INSERT INTO {} (name, client, added) VALUES (?1, ?2, ?3)",
+                 SQL_TABLEN_PRJ),
+        params![name, client, today()])?;
+
+    Ok(db.last_insert_rowid() as i32)
+}
+
+/// assign an activity to a project (`None` removes it from its project)
+pub fn assign(db : &Connection, actid : i32, project : Option<i32>)
+    -> Result<(), Box<dyn error::Error>>
+{
+    if let Some(project) = project
+    {
+        exists(db, SQL_TABLEN_PRJ, project).map_err(|_| "No such project")?;
+    }
+
+    let changed = db.execute(
+        &format!("UPDATE {} SET project = ?1 WHERE id = ?2", SQL_TABLEN_ACT),
+        params![project, actid])?;
+
+    if changed == 0
+    {
+        return Err("No such activity in db".into());
+    }
+
+    Ok(())
+}
+
+/// remove a client; its projects are kept (w/o client)
+pub fn remove_client(db : &Connection, id : i32)
+    -> Result<(), Box<dyn error::Error>>
+{
+    exists(db, SQL_TABLEN_CLI, id).map_err(|_| "No such client")?;
+
+    let tx = db.unchecked_transaction()?;
+    tx.execute(
+        &format!("UPDATE {} SET client = NULL WHERE client = ?1",
+                 SQL_TABLEN_PRJ),
+        params![id])?;
+    tx.execute(&format!("DELETE FROM {} WHERE id = ?1", SQL_TABLEN_CLI),
+               params![id])?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// remove a project; its activities are kept (w/o project)
+pub fn remove_project(db : &Connection, id : i32)
+    -> Result<(), Box<dyn error::Error>>
+{
+    exists(db, SQL_TABLEN_PRJ, id).map_err(|_| "No such project")?;
+
+    let tx = db.unchecked_transaction()?;
+    tx.execute(
+        &format!("UPDATE {} SET project = NULL WHERE project = ?1",
+                 SQL_TABLEN_ACT),
+        params![id])?;
+    tx.execute(&format!("DELETE FROM {} WHERE id = ?1", SQL_TABLEN_PRJ),
+               params![id])?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// given user input retrieves the id of a client (id or exact name)
+pub fn find_client(db : &Connection, input : &str)
+    -> Result<i32, Box<dyn error::Error>>
+{
+    get_clients(db)?
+        .iter()
+        .find(|c| c.name == input || input.parse() == Ok(c.id))
+        .map(|c| c.id)
+        .ok_or_else(|| format!("No such client: {}", input).into())
+}
+
+/// given user input retrieves the id of a project (id or exact name)
+pub fn find_project(db : &Connection, input : &str)
+    -> Result<i32, Box<dyn error::Error>>
+{
+    get_projects(db)?
+        .iter()
+        .find(|p| p.name == input || input.parse() == Ok(p.id))
+        .map(|p| p.id)
+        .ok_or_else(|| format!("No such project: {}", input).into())
+}
+
+/// names have to be non-empty and unique within their table
+pub(crate) fn check_name<'a>(db : &Connection, table : &str, name : &'a str)
+    -> Result<&'a str, Box<dyn error::Error>>
+{
+    let name = name.trim();
+
+    if name.is_empty()
+    {
+        return Err("Name can't be empty".into());
+    }
+
+    let taken : i32 = db.query_row(
+        &format!("SELECT COUNT(*) FROM {} WHERE name = ?1", table),
+        params![name],
+        |row| row.get(0))?;
+
+    if taken > 0
+    {
+        return Err(format!("{} already exists", name).into());
+    }
+
+    Ok(name)
+}
+
+fn exists(db : &Connection, table : &str, id : i32) -> rusqlite::Result<i32>
+{
+    db.query_row(&format!("SELECT id FROM {} WHERE id = ?1", table),
+                 params![id],
+                 |row| row.get(0))
+}
+
+fn today() -> String
+{
+    chrono::Local::now().format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn clients_projects_assignment()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+
+        let acme = add_client(&db, "ACME").unwrap();
+        assert!(add_client(&db, "ACME").is_err());
+        let web = add_project(&db, "Website", Some(acme)).unwrap();
+        let own = add_project(&db, "Internal", None).unwrap();
+        assert!(add_project(&db, "Other", Some(42)).is_err());
+
+        assert_eq!(find_client(&db, "ACME").unwrap(), acme);
+        assert_eq!(find_project(&db, &own.to_string()).unwrap(), own);
+        assert!(find_project(&db, "Nope").is_err());
+
+        assign(&db, 1, Some(web)).unwrap();
+        assign(&db, 2, Some(web)).unwrap();
+        assign(&db, 3, Some(own)).unwrap();
+        assert!(assign(&db, 4, Some(42)).is_err());
+
+        let activities = crate::db::get_activities(&db, true).unwrap();
+        assert_eq!(activities[0].project, Some(web));
+        assert_eq!(activities[3].project, None);
+
+        // removal detaches, keeps everything below
+        remove_project(&db, web).unwrap();
+        let activities = crate::db::get_activities(&db, true).unwrap();
+        assert_eq!(activities[0].project, None);
+        assert_eq!(activities.len(), 4);
+
+        remove_client(&db, acme).unwrap();
+        assert!(get_clients(&db).unwrap().is_empty());
+        assert_eq!(get_projects(&db).unwrap().len(), 1);
+    }
+}
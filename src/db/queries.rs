@@ -1,3 +1,6 @@
+// creation queries below are the layout at the time of a table's creation;
+// any later change to the schema is a migration (see migrations.rs)
+
 pub const SQL_TABLEN_ACT : &str = "tt_activities";
 // CAREFUL: other tables reference activities table BY NAME!
 // PRIMARY implies NOT NULL and UNIQUE
@@ -23,6 +26,92 @@ pub const SQL_CREATE_HIS : &str =
     FOREIGN KEY (id) REFERENCES tt_activities(id)
     )";
 
+// running timer, at most one row
+// started: RFC 3339 timestamp of the beginning of the current work/break phase
+pub const SQL_TABLEN_TMR : &str = "tt_timer";
+pub const SQL_CREATE_TMR : &str =
+"CREATE TABLE tt_timer (
+    id INTEGER NOT NULL,
+    started TEXT NOT NULL,
+    onbreak INTEGER NOT NULL DEFAULT 0,
+    interactive INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (id) REFERENCES tt_activities(id)
+    )";
+
+// every tracked/entered interval; tt_history is derived from its work rows
+// started, ended: UTC timestamps (YYYY-MM-DDTHH:MM:SSZ)
+// kind: work | break; source: timer | manual | import | legacy
+pub const SQL_TABLEN_SES : &str = "tt_sessions";
+pub const SQL_CREATE_SES : &str =
+"CREATE TABLE tt_sessions (
+    sid INTEGER PRIMARY KEY,
+    id INTEGER NOT NULL,
+    started TEXT NOT NULL,
+    ended TEXT NOT NULL,
+    kind TEXT NOT NULL DEFAULT 'work',
+    source TEXT NOT NULL,
+    FOREIGN KEY (id) REFERENCES tt_activities(id)
+    )";
+
+// grouping above activities: client -> project -> activity;
+// projects w/o client and activities w/o project (tt_activities.project
+// NULL) are fine
+pub const SQL_TABLEN_CLI : &str = "tt_clients";
+pub const SQL_CREATE_CLI : &str =
+"CREATE TABLE tt_clients (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    added TEXT NOT NULL
+    )";
+
+pub const SQL_TABLEN_PRJ : &str = "tt_projects";
+pub const SQL_CREATE_PRJ : &str =
+"CREATE TABLE tt_projects (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    client INTEGER,
+    added TEXT NOT NULL,
+    FOREIGN KEY (client) REFERENCES tt_clients(id)
+    )";
+
+// free-form tags, attached to sessions (join table); a session's tags go
+// w/ it when it's removed
+pub const SQL_TABLEN_TAG : &str = "tt_tags";
+pub const SQL_CREATE_TAG : &str =
+"CREATE TABLE tt_tags (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+    )";
+
+pub const SQL_TABLEN_STG : &str = "tt_sessiontags";
+pub const SQL_CREATE_STG : &str =
+"CREATE TABLE tt_sessiontags (
+    sid INTEGER NOT NULL,
+    tag INTEGER NOT NULL,
+    PRIMARY KEY (sid, tag),
+    FOREIGN KEY (sid) REFERENCES tt_sessions(sid) ON DELETE CASCADE,
+    FOREIGN KEY (tag) REFERENCES tt_tags(id) ON DELETE CASCADE
+    )";
+
+// user settings as key/value pairs (see db::settings)
+pub const SQL_TABLEN_SET : &str = "tt_settings";
+pub const SQL_CREATE_SET : &str =
+"CREATE TABLE tt_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+    )";
+
+// hour goals per day, week or month of an activity, activity 0 being the
+// overall goal of all activities together (see db::goals)
+pub const SQL_TABLEN_GOL : &str = "tt_goals";
+pub const SQL_CREATE_GOL : &str =
+"CREATE TABLE tt_goals (
+    activity INTEGER NOT NULL,
+    period TEXT NOT NULL,
+    hours REAL NOT NULL,
+    PRIMARY KEY (activity, period)
+    )";
+
 /*
  * tables `tt_statweekly`, `tt_statmonthly`, `tt_statyearly` once existed,
  * but have been removed; trivial to compute from `tt_history`;
@@ -39,5 +128,13 @@ mod tests
     {
         assert!(SQL_CREATE_ACT.to_string().contains(SQL_TABLEN_ACT));
         assert!(SQL_CREATE_HIS.to_string().contains(SQL_TABLEN_HIS));
+        assert!(SQL_CREATE_TMR.to_string().contains(SQL_TABLEN_TMR));
+        assert!(SQL_CREATE_SES.to_string().contains(SQL_TABLEN_SES));
+        assert!(SQL_CREATE_CLI.to_string().contains(SQL_TABLEN_CLI));
+        assert!(SQL_CREATE_PRJ.to_string().contains(SQL_TABLEN_PRJ));
+        assert!(SQL_CREATE_TAG.to_string().contains(SQL_TABLEN_TAG));
+        assert!(SQL_CREATE_STG.to_string().contains(SQL_TABLEN_STG));
+        assert!(SQL_CREATE_SET.to_string().contains(SQL_TABLEN_SET));
+        assert!(SQL_CREATE_GOL.to_string().contains(SQL_TABLEN_GOL));
     }
 }
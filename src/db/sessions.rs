@@ -0,0 +1,590 @@
+//! interval-level record of every work/break block (sessions table);
+//! `tt_history` is derived from it: every work session entered here is also
+//! entered into the history table (see `db::enter_into_db`)
+
+use std::error;
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use rusqlite::{Connection, params};
+use super::helpers::local_midnight;
+use super::queries::*;
+
+/// timestamps are stored as UTC, in a (lexicographically sortable) format
+const UTC_FORMAT : &str = "%Y-%m-%dT%H:%M:%SZ";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Work,
+    Break,
+}
+
+/// where a session comes from; `Legacy` sessions have been created from
+/// day totals which predate the sessions table (midnight + hours on day)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Timer,
+    Manual,
+    Import,
+    Legacy,
+}
+
+impl Kind
+{
+    pub fn as_str(&self) -> &'static str
+    {
+        match self
+        {
+            Kind::Work  => "work",
+            Kind::Break => "break",
+        }
+    }
+
+    pub fn parse(s : &str) -> Option<Kind>
+    {
+        match s
+        {
+            "work"  => Some(Kind::Work),
+            "break" => Some(Kind::Break),
+            _       => None,
+        }
+    }
+}
+
+impl Source
+{
+    pub fn as_str(&self) -> &'static str
+    {
+        match self
+        {
+            Source::Timer  => "timer",
+            Source::Manual => "manual",
+            Source::Import => "import",
+            Source::Legacy => "legacy",
+        }
+    }
+
+    pub fn parse(s : &str) -> Option<Source>
+    {
+        match s
+        {
+            "timer"  => Some(Source::Timer),
+            "manual" => Some(Source::Manual),
+            "import" => Some(Source::Import),
+            "legacy" => Some(Source::Legacy),
+            _        => None,
+        }
+    }
+}
+
+/// representing a row from Sessions table
+#[derive(Debug, Clone)]
+pub struct SessionRow {
+    pub sid: i64,
+    pub id: i32,
+    pub started: DateTime<Local>,
+    pub ended: DateTime<Local>,
+    pub kind: Kind,
+    pub source: Source,
+    pub note: Option<String>,
+}
+
+impl SessionRow
+{
+    pub fn hours(&self) -> f64
+    {
+        self.ended.signed_duration_since(self.started).num_seconds() as f64
+            / 3600.
+    }
+}
+
+pub fn to_utc_string(dt : &DateTime<Local>) -> String
+{
+    dt.with_timezone(&Utc).format(UTC_FORMAT).to_string()
+}
+
+pub fn from_utc_string(s : &str)
+    -> Result<DateTime<Local>, Box<dyn error::Error>>
+{
+    let naive = chrono::NaiveDateTime::parse_from_str(s, UTC_FORMAT)?;
+    Ok(Utc.from_utc_datetime(&naive).with_timezone(&Local))
+}
+
+/// enter a session; work sessions are entered into the history table as well
+/// (handling midnight turnover etc., see `db::enter_into_db`);
+/// returns the id of the newly created session
+pub fn enter(
+    db      : &Connection,
+    started : &DateTime<Local>,
+    ended   : &DateTime<Local>,
+    actid   : i32,
+    kind    : Kind,
+    source  : Source,
+    ) -> Result<i64, Box<dyn error::Error>>
+{
+    check(started, ended, kind)?;
+
+    // callers may already have opened a transaction (see db::timer)
+    let tx = if db.is_autocommit() {
+        Some(db.unchecked_transaction()?)
+    } else {
+        None
+    };
+
+    let sid = insert(db, started, ended, actid, kind, source)?;
+
+    if kind == Kind::Work
+    {
+        super::enter_into_db(db, started, ended, actid)?;
+    }
+
+    if let Some(tx) = tx { tx.commit()?; }
+
+    Ok(sid)
+}
+
+/// whether a session could be entered (see enter()), for callers to check
+/// before making other changes
+pub fn check(started : &DateTime<Local>, ended : &DateTime<Local>, kind : Kind)
+    -> Result<(), Box<dyn error::Error>>
+{
+    if ended < started
+    {
+        return Err("Session can't end before it started".into());
+    }
+    if kind == Kind::Work &&
+        ended.signed_duration_since(started) >= Duration::hours(24)
+    {
+        return Err("Times of >= 24 hours aren't supported".into());
+    }
+
+    Ok(())
+}
+
+/// retrieve all sessions overlapping the (local) dates from..=to
+pub fn get(db : &Connection, from : NaiveDate, to : NaiveDate)
+    -> Result<Vec<SessionRow>, Box<dyn error::Error>>
+{
+    let beg = to_utc_string(&local_midnight(from)?);
+    let end = to_utc_string(&local_midnight(to + Duration::days(1))?);
+
+    query(db,
+          &format!("SELECT sid, id, started, ended, kind, source, note FROM {}
+                   WHERE ended > ?1 AND started < ?2
+                   ORDER BY started ASC", SQL_TABLEN_SES),
+          params![beg, end])
+}
+
+/// retrieve a single session by its id
+pub fn get_by_sid(db : &Connection, sid : i64)
+    -> Result<SessionRow, Box<dyn error::Error>>
+{
+    query(db,
+          &format!("SELECT sid, id, started, ended, kind, source, note FROM {}
+                   WHERE sid = ?1", SQL_TABLEN_SES),
+          params![sid])?
+        .pop()
+        .ok_or_else(|| "No such session".into())
+}
+
+/// set (or with `None`/empty text remove) the note of a session
+pub fn set_note(db : &Connection, sid : i64, note : Option<&str>)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let note = note.map(|n| n.trim()).filter(|n| !n.is_empty());
+
+    let changed = db.execute(
+        &format!("UPDATE {} SET note = ?1 WHERE sid = ?2", SQL_TABLEN_SES),
+        params![note, sid])?;
+
+    if changed == 0
+    {
+        return Err("No such session".into());
+    }
+
+    Ok(())
+}
+
+/// notes of the work sessions of an activity on a (local) date
+pub fn notes_of_day(db : &Connection, actid : i32, date : NaiveDate)
+    -> Result<Vec<String>, Box<dyn error::Error>>
+{
+    Ok(get(db, date, date)?
+        .into_iter()
+        .filter(|s| s.id == actid && s.kind == Kind::Work)
+        .filter_map(|s| s.note)
+        .collect())
+}
+
+/// remove a session; hours of a work session are deducted from the history
+/// and activities table again
+pub fn remove(db : &Connection, sid : i64)
+    -> Result<SessionRow, Box<dyn error::Error>>
+{
+    let session = get_by_sid(db, sid)?;
+    let tx = db.unchecked_transaction()?;
+
+    if session.kind == Kind::Work
+    {
+        super::deduct_from_db(&tx, &session.started, &session.ended,
+                              session.id)?;
+    }
+
+    tx.execute(&format!("DELETE FROM {} WHERE sid = ?1", SQL_TABLEN_SES),
+               params![sid])?;
+    tx.commit()?;
+
+    Ok(session)
+}
+
+/// remove all work sessions of an activity from a (local) date; sessions
+/// crossing midnight are clipped such that they only cover the other day;
+/// used whenever a day total in the history table is removed/changed
+pub fn clear_day(db : &Connection, actid : i32, date : NaiveDate)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let daybeg = local_midnight(date)?;
+    let dayend = local_midnight(date + Duration::days(1))?;
+
+    for session in get(db, date, date)?
+    {
+        if session.id != actid || session.kind != Kind::Work { continue; }
+
+        if session.started < daybeg
+        {
+            db.execute(
+                &format!("UPDATE {} SET ended = ?1 WHERE sid = ?2",
+                         SQL_TABLEN_SES),
+                params![to_utc_string(&daybeg), session.sid])?;
+        }
+        else if session.ended > dayend
+        {
+            db.execute(
+                &format!("UPDATE {} SET started = ?1 WHERE sid = ?2",
+                         SQL_TABLEN_SES),
+                params![to_utc_string(&dayend), session.sid])?;
+        }
+        else
+        {
+            db.execute(
+                &format!("DELETE FROM {} WHERE sid = ?1", SQL_TABLEN_SES),
+                params![session.sid])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// part of a session taken off by trim_day(): session as it was, begin, end
+pub type Piece = (SessionRow, DateTime<Local>, DateTime<Local>);
+
+/// take `hours` off the work sessions of an activity on a (local) date,
+/// latest first: sessions are shortened (crossing midnight: only their part
+/// on `date`) or removed once nothing is left of them; history and total
+/// hours are deducted accordingly; returns the taken pieces
+pub fn trim_day(db : &Connection, actid : i32, date : NaiveDate, hours : f64)
+    -> Result<Vec<Piece>, Box<dyn error::Error>>
+{
+    let daybeg = local_midnight(date)?;
+    let dayend = local_midnight(date + Duration::days(1))?;
+    let mut remaining = Duration::seconds((hours * 3600.).round() as i64);
+    let mut taken = Vec::new();
+
+    let tx = if db.is_autocommit() {
+        Some(db.unchecked_transaction()?)
+    } else {
+        None
+    };
+
+    let mut sessions : Vec<SessionRow> = get(db, date, date)?
+        .into_iter()
+        .filter(|s| s.id == actid && s.kind == Kind::Work)
+        .collect();
+    sessions.reverse();
+
+    for session in sessions
+    {
+        if remaining <= Duration::zero() { break; }
+
+        let beg = session.started.max(daybeg);
+        let end = session.ended.min(dayend);
+        let take = remaining.min(end.signed_duration_since(beg));
+        if take <= Duration::zero() { continue; }
+
+        // piece taken off: end of the part on `date`, unless the session
+        // continues into the next day, then its beginning
+        let (piecebeg, pieceend) = if session.ended > dayend {
+            (beg, beg + take)
+        } else {
+            (end - take, end)
+        };
+
+        super::deduct_from_db(db, &piecebeg, &pieceend, actid)?;
+
+        if piecebeg == session.started && pieceend == session.ended
+        {
+            db.execute(
+                &format!("DELETE FROM {} WHERE sid = ?1", SQL_TABLEN_SES),
+                params![session.sid])?;
+        }
+        else if piecebeg == session.started
+        {
+            db.execute(
+                &format!("UPDATE {} SET started = ?1 WHERE sid = ?2",
+                         SQL_TABLEN_SES),
+                params![to_utc_string(&pieceend), session.sid])?;
+        }
+        else if pieceend == session.ended
+        {
+            db.execute(
+                &format!("UPDATE {} SET ended = ?1 WHERE sid = ?2",
+                         SQL_TABLEN_SES),
+                params![to_utc_string(&piecebeg), session.sid])?;
+        }
+        else
+        {
+            return Err("Can't take time off the middle of a session".into());
+        }
+
+        remaining -= take;
+        taken.push((session, piecebeg, pieceend));
+    }
+
+    if remaining > Duration::seconds(1)
+    {
+        return Err("Not that many hours tracked on that day".into());
+    }
+
+    if let Some(tx) = tx { tx.commit()?; }
+
+    Ok(taken)
+}
+
+/// split a session in two at `at`; the second part becomes a new session
+/// (same activity, kind, source, note and tags); returns its id
+pub fn split(db : &Connection, sid : i64, at : &DateTime<Local>)
+    -> Result<i64, Box<dyn error::Error>>
+{
+    let session = get_by_sid(db, sid)?;
+
+    if *at <= session.started || *at >= session.ended
+    {
+        return Err("Split time has to lie within the session".into());
+    }
+
+    let tx = db.unchecked_transaction()?;
+
+    tx.execute(
+        &format!("UPDATE {} SET ended = ?1 WHERE sid = ?2", SQL_TABLEN_SES),
+        params![to_utc_string(at), sid])?;
+
+    // history is unaffected, the time stays w/ the same activity
+    let newsid = insert(&tx, at, &session.ended, session.id,
+                        session.kind, session.source)?;
+    set_note(&tx, newsid, session.note.as_deref())?;
+    super::tags::add(&tx, newsid, &super::tags::of_session(&tx, sid)?)?;
+
+    tx.commit()?;
+
+    Ok(newsid)
+}
+
+/// move a session to another activity (history and total hours of both
+/// activities are adjusted)
+pub fn reassign(db : &Connection, sid : i64, actid : i32)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let session = get_by_sid(db, sid)?;
+    super::get_activityname_for_id(db, actid)?;
+
+    if session.id == actid { return Ok(()); }
+
+    let tx = db.unchecked_transaction()?;
+
+    if session.kind == Kind::Work
+    {
+        super::deduct_from_db(&tx, &session.started, &session.ended,
+                              session.id)?;
+        super::enter_into_db(&tx, &session.started, &session.ended, actid)?;
+    }
+
+    tx.execute(&format!("UPDATE {} SET id = ?1 WHERE sid = ?2", SQL_TABLEN_SES),
+               params![actid, sid])?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn insert(
+    db      : &Connection,
+    started : &DateTime<Local>,
+    ended   : &DateTime<Local>,
+    actid   : i32,
+    kind    : Kind,
+    source  : Source,
+    ) -> Result<i64, Box<dyn error::Error>>
+{
+    db.execute(
+        &format!("INSERT INTO {} (id, started, ended, kind, source)
+                 VALUES (?1, ?2, ?3, ?4, ?5)", SQL_TABLEN_SES),
+        params![actid, to_utc_string(started), to_utc_string(ended),
+                kind.as_str(), source.as_str()])?;
+
+    Ok(db.last_insert_rowid())
+}
+
+fn query<P : rusqlite::Params>(db : &Connection, sql : &str, params : P)
+    -> Result<Vec<SessionRow>, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(sql)?;
+
+    let rows = stmt.query_map(params, |row| {
+        Ok((row.get::<_, i64>(0)?,
+            row.get::<_, i32>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, String>(5)?,
+            row.get::<_, Option<String>>(6)?))
+    })?;
+
+    let mut sessions = Vec::new();
+
+    for row in rows
+    {
+        let (sid, id, started, ended, kind, source, note) = row?;
+
+        sessions.push(SessionRow {
+            sid,
+            id,
+            started: from_utc_string(&started)?,
+            ended: from_utc_string(&ended)?,
+            kind: Kind::parse(&kind).ok_or("Invalid session kind")?,
+            source: Source::parse(&source).ok_or("Invalid session source")?,
+            note,
+        });
+    }
+
+    Ok(sessions)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test;
+
+    fn hoursonday(db : &Connection, id : i32, date : &str) -> f64
+    {
+        db.query_row(
+            &format!("SELECT hoursonday FROM {} WHERE id=?1 AND date=?2",
+                     SQL_TABLEN_HIS),
+            params![id, date],
+            |row| row.get(0)).unwrap_or(0.)
+    }
+
+    #[test]
+    fn enter_and_remove()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        // 22:00 to 02:00, break in between, 09:00 to 10:30
+        let beg = Local.with_ymd_and_hms(2024, 01, 10, 22, 0, 0).unwrap();
+        let night = enter(&db, &beg, &(beg + Duration::hours(4)), 1,
+                          Kind::Work, Source::Timer).unwrap();
+        enter(&db, &(beg + Duration::hours(4)), &(beg + Duration::hours(11)),
+              1, Kind::Break, Source::Timer).unwrap();
+        enter(&db, &(beg + Duration::hours(11)),
+              &(beg + Duration::minutes(750)), 1,
+              Kind::Work, Source::Manual).unwrap();
+
+        assert!((hoursonday(&db, 1, "2024-01-10") - 2.).abs() <= epsilon);
+        assert!((hoursonday(&db, 1, "2024-01-11") - 3.5).abs() <= epsilon);
+
+        let day = NaiveDate::from_ymd_opt(2024, 01, 11).unwrap();
+        let sessions = get(&db, day, day).unwrap();
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions[0].started, beg);
+        assert_eq!(sessions[1].kind, Kind::Break);
+        assert_eq!(sessions[2].source, Source::Manual);
+        assert!((sessions[2].hours() - 1.5).abs() <= epsilon);
+
+        set_note(&db, sessions[2].sid, Some("  code review ")).unwrap();
+        set_note(&db, sessions[1].sid, Some("lunch")).unwrap();
+        assert_eq!(notes_of_day(&db, 1, day).unwrap(), vec!["code review"]);
+        set_note(&db, sessions[2].sid, Some("")).unwrap();
+        assert!(get_by_sid(&db, sessions[2].sid).unwrap().note.is_none());
+
+        // removal deducts from both days
+        remove(&db, night).unwrap();
+        assert!((hoursonday(&db, 1, "2024-01-10") - 0.).abs() <= epsilon);
+        assert!((hoursonday(&db, 1, "2024-01-11") - 1.5).abs() <= epsilon);
+        assert!(remove(&db, night).is_err());
+
+        assert!(enter(&db, &beg, &(beg + Duration::hours(24)), 1,
+                      Kind::Work, Source::Manual).is_err());
+    }
+
+    #[test]
+    fn trim_split_reassign()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        // 22:00 to 02:00 and 09:00 to 10:00
+        let beg = Local.with_ymd_and_hms(2024, 01, 10, 22, 0, 0).unwrap();
+        let night = enter(&db, &beg, &(beg + Duration::hours(4)), 1,
+                          Kind::Work, Source::Timer).unwrap();
+        enter(&db, &(beg + Duration::hours(11)),
+              &(beg + Duration::hours(12)), 1,
+              Kind::Work, Source::Timer).unwrap();
+
+        // latest first; the night session loses its end
+        let day = NaiveDate::from_ymd_opt(2024, 01, 11).unwrap();
+        let taken = trim_day(&db, 1, day, 1.5).unwrap();
+        assert_eq!(taken.len(), 2);
+        assert_eq!(taken[1].1, beg + Duration::minutes(210));
+        assert_eq!(taken[1].2, beg + Duration::hours(4));
+        assert!((hoursonday(&db, 1, "2024-01-11") - 1.5).abs() <= epsilon);
+        assert!((hoursonday(&db, 1, "2024-01-10") - 2.).abs() <= epsilon);
+        assert_eq!(get(&db, day, day).unwrap().len(), 1);
+        assert!(trim_day(&db, 1, day, 2.).is_err());
+        assert!((hoursonday(&db, 1, "2024-01-11") - 1.5).abs() <= epsilon);
+
+        // split at 01:00, second part goes to B
+        set_note(&db, night, Some("deploy")).unwrap();
+        let at = beg + Duration::hours(3);
+        assert!(split(&db, night, &(beg - Duration::hours(1))).is_err());
+        let second = split(&db, night, &at).unwrap();
+        assert_eq!(get_by_sid(&db, second).unwrap().note.unwrap(), "deploy");
+        assert!((hoursonday(&db, 1, "2024-01-11") - 1.5).abs() <= epsilon);
+
+        reassign(&db, second, 2).unwrap();
+        assert!((hoursonday(&db, 1, "2024-01-11") - 1.).abs() <= epsilon);
+        assert!((hoursonday(&db, 2, "2024-01-11") - 0.5).abs() <= epsilon);
+        assert!(reassign(&db, second, 42).is_err());
+    }
+
+    #[test]
+    fn clear_day_clips()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+
+        let beg = Local.with_ymd_and_hms(2024, 01, 10, 22, 0, 0).unwrap();
+        enter(&db, &beg, &(beg + Duration::hours(4)), 2,
+              Kind::Work, Source::Timer).unwrap();
+
+        clear_day(&db, 2, NaiveDate::from_ymd_opt(2024, 01, 11).unwrap())
+            .unwrap();
+
+        let day = NaiveDate::from_ymd_opt(2024, 01, 10).unwrap();
+        let sessions = get(&db, day, day).unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!((sessions[0].hours() - 2.).abs() <= 0.001);
+    }
+}
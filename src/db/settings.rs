@@ -0,0 +1,188 @@
+//! user settings, kept as key/value pairs in the db; currently the hour
+//! thresholds of the yearly statistics (see stat::helpers::YearCounts) and
+//! of streaks (see stat::helpers::retrieve_streaks)
+
+use std::error;
+use rusqlite::{Connection, OptionalExtension, params};
+use super::queries::*;
+
+pub const KEY_DAY_THRESHOLDS  : &str = "day_thresholds";
+pub const KEY_WEEK_THRESHOLDS : &str = "week_thresholds";
+pub const KEY_STREAK_HOURS    : &str = "streak_hours";
+pub const KEY_STREAK_ACTIVITY : &str = "streak_activity_hours";
+
+/// thresholds used as long as the user hasn't defined any
+pub const DEFAULT_DAY_THRESHOLDS  : &[f64] = &[4., 8., 10., 12.];
+pub const DEFAULT_WEEK_THRESHOLDS : &[f64] = &[20., 40., 50., 60., 70., 80.];
+pub const DEFAULT_STREAK_HOURS    : f64 = 4.; // total of a day
+pub const DEFAULT_STREAK_ACTIVITY : f64 = 1.; // of a single activity
+
+/// retrieve a setting (`None` if not set)
+pub fn get(db : &Connection, key : &str)
+    -> Result<Option<String>, Box<dyn error::Error>>
+{
+    let value = db.query_row(
+        &format!("SELECT value FROM {} WHERE key = ?1", SQL_TABLEN_SET),
+        params![key],
+        |row| row.get(0))
+        .optional()?;
+
+    Ok(value)
+}
+
+/// store a setting (replacing a previous value)
+pub fn set(db : &Connection, key : &str, value : &str)
+    -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(
+        &format!("INSERT OR REPLACE INTO {} (key, value) VALUES (?1, ?2)",
+                 SQL_TABLEN_SET),
+        params![key, value])?;
+
+    Ok(())
+}
+
+/// remove a setting, reverting it to its default
+pub fn reset(db : &Connection, key : &str)
+    -> Result<(), Box<dyn error::Error>>
+{
+    db.execute(&format!("DELETE FROM {} WHERE key = ?1", SQL_TABLEN_SET),
+               params![key])?;
+
+    Ok(())
+}
+
+/// hours a day needs for the yearly statistics' `% of days w/ x hrs+`
+pub fn day_thresholds(db : &Connection)
+    -> Result<Vec<f64>, Box<dyn error::Error>>
+{
+    thresholds(db, KEY_DAY_THRESHOLDS, DEFAULT_DAY_THRESHOLDS)
+}
+
+/// hours a week needs for the yearly statistics' `% of weeks w/ x hrs+`
+pub fn week_thresholds(db : &Connection)
+    -> Result<Vec<f64>, Box<dyn error::Error>>
+{
+    thresholds(db, KEY_WEEK_THRESHOLDS, DEFAULT_WEEK_THRESHOLDS)
+}
+
+/// hours a day needs (in total) to count towards a streak
+pub fn streak_hours(db : &Connection) -> Result<f64, Box<dyn error::Error>>
+{
+    threshold(db, KEY_STREAK_HOURS, DEFAULT_STREAK_HOURS)
+}
+
+/// hours a day needs on a single activity to count towards its streak
+pub fn streak_activity_hours(db : &Connection)
+    -> Result<f64, Box<dyn error::Error>>
+{
+    threshold(db, KEY_STREAK_ACTIVITY, DEFAULT_STREAK_ACTIVITY)
+}
+
+/// store a single threshold given as user input
+pub fn set_threshold(db : &Connection, key : &str, input : &str)
+    -> Result<f64, Box<dyn error::Error>>
+{
+    let thresholds = parse_thresholds(input)?;
+
+    if thresholds.len() != 1
+    {
+        return Err("Only a single threshold is expected".into());
+    }
+
+    set(db, key, &thresholds[0].to_string())?;
+
+    Ok(thresholds[0])
+}
+
+/// store thresholds given as user input (see parse_thresholds())
+pub fn set_thresholds(db : &Connection, key : &str, input : &str)
+    -> Result<Vec<f64>, Box<dyn error::Error>>
+{
+    let thresholds = parse_thresholds(input)?;
+
+    set(db, key, &thresholds.iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<_>>()
+        .join(","))?;
+
+    Ok(thresholds)
+}
+
+/// parse thresholds separated by commas and/or whitespace (e.g. `2, 4, 8`);
+/// they have to be positive hours, duplicates are dropped, result is sorted
+pub fn parse_thresholds(input : &str)
+    -> Result<Vec<f64>, Box<dyn error::Error>>
+{
+    let mut thresholds = Vec::new();
+
+    for part in input.split(|c : char| c == ',' || c.is_whitespace())
+    {
+        if part.is_empty() { continue; }
+
+        let hours : f64 = part.parse()
+            .map_err(|_| format!("Invalid threshold: {}", part))?;
+
+        if !hours.is_finite() || hours <= 0.
+        {
+            return Err(format!("Thresholds have to be positive hours: {}",
+                               part).into());
+        }
+        if !thresholds.contains(&hours) { thresholds.push(hours); }
+    }
+
+    if thresholds.is_empty()
+    {
+        return Err("At least one threshold is needed".into());
+    }
+
+    thresholds.sort_by(|a, b| a.total_cmp(b));
+
+    Ok(thresholds)
+}
+
+fn thresholds(db : &Connection, key : &str, default : &[f64])
+    -> Result<Vec<f64>, Box<dyn error::Error>>
+{
+    match get(db, key)?
+    {
+        Some(value) => parse_thresholds(&value),
+        None        => Ok(default.to_vec()),
+    }
+}
+
+fn threshold(db : &Connection, key : &str, default : f64)
+    -> Result<f64, Box<dyn error::Error>>
+{
+    Ok(thresholds(db, key, &[default])?[0])
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn thresholds_stored()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+
+        assert_eq!(day_thresholds(&db).unwrap(), DEFAULT_DAY_THRESHOLDS);
+        assert!(set_thresholds(&db, KEY_DAY_THRESHOLDS, "4, -2").is_err());
+        assert!(set_thresholds(&db, KEY_DAY_THRESHOLDS, " ").is_err());
+
+        set_thresholds(&db, KEY_DAY_THRESHOLDS, "4 2,2 1.5").unwrap();
+        assert_eq!(day_thresholds(&db).unwrap(), vec![1.5, 2., 4.]);
+        assert_eq!(week_thresholds(&db).unwrap(), DEFAULT_WEEK_THRESHOLDS);
+
+        reset(&db, KEY_DAY_THRESHOLDS).unwrap();
+        assert_eq!(day_thresholds(&db).unwrap(), DEFAULT_DAY_THRESHOLDS);
+
+        assert!(set_threshold(&db, KEY_STREAK_HOURS, "1 2").is_err());
+        set_threshold(&db, KEY_STREAK_HOURS, "0.5").unwrap();
+        assert_eq!(streak_hours(&db).unwrap(), 0.5);
+        assert_eq!(streak_activity_hours(&db).unwrap(), 1.);
+    }
+}
@@ -2,7 +2,7 @@ use core::ops::Add;
 use crate::db::queries::*;
 use std::error;
 use rusqlite::{Connection, Error, params};
-use chrono::{Datelike, Local, TimeZone, NaiveDate, Weekday};
+use chrono::{Datelike, Local, TimeZone, Timelike, NaiveDate, Weekday};
 
 fn max_iso_week(year: i32) -> u32
 {
@@ -17,19 +17,29 @@ fn max_iso_week(year: i32) -> u32
 pub fn firstentry_datetime(db : &Connection)
     -> Result<chrono::DateTime<Local>, Box<dyn error::Error>>
 {
+    firstentry_datetime_of(db, None)
+}
+
+/// like firstentry_datetime(), but of the given activities only (all if
+/// `None`)
+pub fn firstentry_datetime_of(db : &Connection, activities : Option<&[i32]>)
+    -> Result<chrono::DateTime<Local>, Box<dyn error::Error>>
+{
+    let cond = activities_condition(activities);
+
 	let firstyy : i32 = db.query_row(
-        &format!("SELECT year  FROM {} ORDER BY date ASC LIMIT 1", 
-                 SQL_TABLEN_HIS), [], |row| row.get(0))
+        &format!("SELECT year  FROM {} WHERE {} ORDER BY date ASC LIMIT 1", 
+                 SQL_TABLEN_HIS, cond), [], |row| row.get(0))
         .unwrap_or(-1);
 
     if firstyy == -1 { return Err("No such entry".into()); }
 
 	let firstmm : u32 = db.query_row(
-        &format!("SELECT month FROM {} ORDER BY date ASC LIMIT 1", 
-                 SQL_TABLEN_HIS), [], |row| row.get(0)).unwrap();
+        &format!("SELECT month FROM {} WHERE {} ORDER BY date ASC LIMIT 1", 
+                 SQL_TABLEN_HIS, cond), [], |row| row.get(0)).unwrap();
 	let firstdd : u32 = db.query_row(
-        &format!("SELECT day   FROM {} ORDER BY date ASC LIMIT 1", 
-                 SQL_TABLEN_HIS), [], |row| row.get(0)).unwrap();
+        &format!("SELECT day   FROM {} WHERE {} ORDER BY date ASC LIMIT 1", 
+                 SQL_TABLEN_HIS, cond), [], |row| row.get(0)).unwrap();
 
     Ok(chrono::Local
         .with_ymd_and_hms(firstyy, firstmm, firstdd, 0, 0, 0)
@@ -113,6 +123,23 @@ pub fn relevantddcount_month_current(
     res as i32
 }
 
+/// days of the range from..=to relevant for averages: neither before the
+/// first entry nor after today
+pub fn relevantddcount_range(
+    firstentry  : &chrono::DateTime<Local>,
+    from 		: NaiveDate,
+    to	 		: NaiveDate,
+    today		: &chrono::DateTime<Local>,
+    ) -> i32
+{
+    let from = from.max(firstentry.date_naive());
+    let to   = to.min(today.date_naive());
+
+    if to < from { return 0; }
+
+    (to - from).num_days() as i32 + 1
+}
+
 /// retrieve total of this week
 /// (from today included up to last Monday included)
 pub fn retrieve_total_this_week(
@@ -348,141 +375,709 @@ pub fn retrieve_total_today(
     }
 }
 
+/// retrieve total of a calendar year
+pub fn retrieve_total_year(
+    db   : &Connection,
+    year : i32,
+    id   : i32,
+    )
+    -> Result<f64, Error>
+{
+    db.query_row(
+        &format!("SELECT COALESCE(SUM(hoursonday), 0.0) FROM {}
+                 WHERE year = ?1 AND id = ?2", SQL_TABLEN_HIS),
+        params![year, id],
+        |row| row.get(0))
+}
+
+/// retrieve total of the dates from..=to (both included)
+pub fn retrieve_total_range(
+    db   : &Connection,
+    from : NaiveDate,
+    to   : NaiveDate,
+    id   : i32,
+    )
+    -> Result<f64, Error>
+{
+    db.query_row(
+        &format!("SELECT COALESCE(SUM(hoursonday), 0.0) FROM {}
+                 WHERE date >= ?1 AND date <= ?2 AND id = ?3",
+                 SQL_TABLEN_HIS),
+        params![from.format("%Y-%m-%d").to_string(),
+                to.format("%Y-%m-%d").to_string(), id],
+        |row| row.get(0))
+}
+
+/// retrieve all time total (as kept in the activities table)
+pub fn retrieve_total_alltime(
+    db : &Connection,
+    id : i32,
+    )
+    -> Result<f64, Error>
+{
+    db.query_row(
+        &format!("SELECT hourstotal FROM {} WHERE id = ?1", SQL_TABLEN_ACT),
+        params![id],
+        |row| row.get(0))
+}
+
+/// periods a series of totals can be broken down into
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Week,  // ISO week (isoweek/isoweekyear of the history table)
+    Month, // calendar month
+}
+
+/// one line of a series: totals of one period, per activity (in the order
+/// the activities were given)
 #[derive(Debug)]
-pub struct YearCounts {
-    pub dd_00_hrs	 : i32,
-    pub dd_04_hrspls : i32,
-    pub dd_08_hrspls : i32,
-    pub dd_10_hrspls : i32,
-    pub dd_12_hrspls : i32,
-    pub dd_14_hrspls : i32,
-    pub dds_relevant : i32,
-    pub wk_00_hrs    : i32,
-    pub wk_10_hrspls : i32,
-    pub wk_20_hrspls : i32,
-    pub wk_30_hrspls : i32,
-    pub wk_40_hrspls : i32,
-    pub wk_50_hrspls : i32,
-    pub wk_60_hrspls : i32,
-    pub wk_70_hrspls : i32,
-    pub wk_80_hrspls : i32,
-    pub wk_90_hrspls : i32,
-    pub wks_relevant : i32,
+pub struct SeriesRow {
+    pub label : String,
+    pub hours : Vec<f64>,
+}
+
+/// retrieve totals per period (week or month) of the given activities, of a
+/// year (for weeks: ISO week year) or all time; every period from the first
+/// to the last one w/ entries is listed, those w/o entries w/ zeros
+pub fn retrieve_series(
+    db     : &Connection,
+    period : Period,
+    year   : Option<i32>,
+    ids    : &[i32],
+    )
+    -> Result<Vec<SeriesRow>, Error>
+{
+    let (yycol, percol) = match period
+    {
+        Period::Week  => ("isoweekyear", "isoweek"),
+        Period::Month => ("year", "month"),
+    };
+
+    let mut stmt = db.prepare(
+        &format!("SELECT {yy}, {per}, id, SUM(hoursonday) FROM {}
+                 WHERE (?1 IS NULL OR {yy} = ?1)
+                 GROUP BY {yy}, {per}, id ORDER BY {yy}, {per}",
+                 SQL_TABLEN_HIS, yy = yycol, per = percol))?;
+
+    let totals : Vec<(i32, u32, i32, f64)> = stmt
+        .query_map(params![year], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?
+        .collect::<Result<_, _>>()?;
+
+    let (first, last) = match (totals.first(), totals.last())
+    {
+        (Some(first), Some(last)) => ((first.0, first.1), (last.0, last.1)),
+        _ => return Ok(Vec::new()),
+    };
+
+    let mut rows = Vec::new();
+    let mut current = first;
+
+    loop
+    {
+        let hours = ids.iter()
+            .map(|id| totals.iter()
+                 .filter(|t| (t.0, t.1) == current && t.2 == *id)
+                 .map(|t| t.3)
+                 .sum())
+            .collect();
+
+        rows.push(SeriesRow {
+            label: match period
+            {
+                Period::Week  => format!("{}-W{:02}", current.0, current.1),
+                Period::Month => format!("{}-{:02}", current.0, current.1),
+            },
+            hours,
+        });
+
+        if current == last { break; }
+
+        // next period, turning over into the next year
+        let max = match period
+        {
+            Period::Week  => max_iso_week(current.0),
+            Period::Month => 12,
+        };
+        current = if current.1 < max { (current.0, current.1 + 1) }
+                  else { (current.0 + 1, 1) };
+    }
+
+    Ok(rows)
+}
+
+/// retrieve total hours of every day from..=to w/ entries, of one activity
+/// or (`None`) all of them
+pub fn retrieve_day_totals(
+    db   : &Connection,
+    from : NaiveDate,
+    to   : NaiveDate,
+    id   : Option<i32>,
+    )
+    -> Result<Vec<(NaiveDate, f64)>, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(
+        &format!("SELECT date, SUM(hoursonday) FROM {}
+                 WHERE date >= ?1 AND date <= ?2 AND (?3 IS NULL OR id = ?3)
+                 GROUP BY date ORDER BY date ASC", SQL_TABLEN_HIS))?;
+
+    let rows : Vec<(String, f64)> = stmt
+        .query_map(params![from.format("%Y-%m-%d").to_string(),
+                           to.format("%Y-%m-%d").to_string(), id],
+                   |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    let mut totals = Vec::new();
+
+    for (date, hours) in rows
+    {
+        totals.push((NaiveDate::parse_from_str(&date, "%Y-%m-%d")?, hours));
+    }
+
+    Ok(totals)
+}
+
+/// totals of one weekday, per activity (in the order the activities were
+/// given), and the number of such days since the first entry
+#[derive(Debug)]
+pub struct WeekdayRow {
+    pub weekday : Weekday,
+    pub days    : i32,
+    pub hours   : Vec<f64>,
+}
+
+/// retrieve totals per weekday (Monday first) of the given activities; days
+/// before the first entry (of any activity) aren't counted, days w/o entries
+/// since then are (dividing the totals by `days` gives averages per day)
+pub fn retrieve_weekdays(
+    db    : &Connection,
+    ids   : &[i32],
+    today : NaiveDate,
+    )
+    -> Result<Vec<WeekdayRow>, Box<dyn error::Error>>
+{
+    let first = match firstentry_datetime(db)
+    {
+        Ok(first) => first.date_naive(),
+        Err(_)    => return Ok(Vec::new()),
+    };
+
+    let mut rows = Vec::new();
+    let mut weekday = Weekday::Mon;
+
+    for _ in 0..7
+    {
+        rows.push(WeekdayRow { weekday, days: 0, hours: vec![0.; ids.len()] });
+        weekday = weekday.succ();
+    }
+
+    for date in first.iter_days().take_while(|date| *date <= today)
+    {
+        rows[date.weekday().num_days_from_monday() as usize].days += 1;
+    }
+
+    let mut stmt = db.prepare(
+        &format!("SELECT id, date, hoursonday FROM {}
+                 WHERE date >= ?1 AND date <= ?2", SQL_TABLEN_HIS))?;
+
+    let entries : Vec<(i32, String, f64)> = stmt
+        .query_map(params![first.format("%Y-%m-%d").to_string(),
+                           today.format("%Y-%m-%d").to_string()],
+                   |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<_, _>>()?;
+
+    for (id, date, hours) in entries
+    {
+        let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")?;
+
+        if let Some(index) = ids.iter().position(|i| *i == id)
+        {
+            let weekday = date.weekday().num_days_from_monday() as usize;
+            rows[weekday].hours[index] += hours;
+        }
+    }
+
+    Ok(rows)
+}
+
+/// retrieve hours per hour of the day (24 buckets, local time) of the given
+/// activities (in the order given) from their work sessions overlapping
+/// from..=to;
+/// only sessions w/ real begin and end times count (tracked or imported),
+/// manual and legacy ones all begin at midnight
+pub fn retrieve_hours_of_day(
+    db   : &Connection,
+    ids  : &[i32],
+    from : NaiveDate,
+    to   : NaiveDate,
+    )
+    -> Result<Vec<[f64; 24]>, Box<dyn error::Error>>
+{
+    use crate::db::sessions::{self, Kind, Source};
+
+    let mut buckets = vec![[0.; 24]; ids.len()];
+
+    for session in sessions::get(db, from, to)?
+    {
+        if session.kind != Kind::Work { continue; }
+        if !matches!(session.source, Source::Timer | Source::Import)
+        {
+            continue;
+        }
+        let index = match ids.iter().position(|id| *id == session.id)
+        {
+            Some(index) => index,
+            None        => continue,
+        };
+
+        // split the session at every full hour
+        let mut current = session.started;
+
+        while current < session.ended
+        {
+            let hour = current
+                - chrono::Duration::minutes(current.minute() as i64)
+                - chrono::Duration::seconds(current.second() as i64)
+                - chrono::Duration::nanoseconds(current.nanosecond() as i64);
+            let next = (hour + chrono::Duration::hours(1)).min(session.ended);
+
+            buckets[index][current.hour() as usize] +=
+                (next - current).num_milliseconds() as f64 / 3_600_000.;
+            current = next;
+        }
+    }
+
+    Ok(buckets)
+}
+
+/// what break statistics are grouped by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakGroup {
+    Day,      // date the sessions began on
+    Week,     // ISO week
+    Activity,
+}
+
+/// work and break time of one group (see retrieve_breaks())
+#[derive(Debug, Default)]
+pub struct BreakRow {
+    pub label  : String,
+    pub work   : f64, // hours
+    pub breaks : f64, // hours
+    pub count  : i32,
+}
+
+impl BreakRow
+{
+    /// break time relative to work time
+    pub fn ratio(&self) -> f64
+    {
+        if self.work > 0. { self.breaks / self.work } else { 0. }
+    }
+
+    /// average length of a break in minutes
+    pub fn avg_minutes(&self) -> f64
+    {
+        if self.count > 0 { self.breaks * 60. / self.count as f64 } else { 0. }
+    }
+}
+
+/// retrieve work and break time of the sessions from..=to, grouped by day,
+/// week (both in order) or activity (by name); only the tracker records
+/// breaks, so only its sessions count (manual entries would skew the ratio)
+pub fn retrieve_breaks(
+    db    : &Connection,
+    from  : NaiveDate,
+    to    : NaiveDate,
+    group : BreakGroup,
+    )
+    -> Result<Vec<BreakRow>, Box<dyn error::Error>>
+{
+    use crate::db::sessions::{self, Kind, Source};
+
+    let mut rows : Vec<BreakRow> = Vec::new();
+
+    for session in sessions::get(db, from, to)?
+    {
+        if session.source != Source::Timer { continue; }
+
+        let date = session.started.date_naive();
+        let label = match group
+        {
+            BreakGroup::Day      => date.format("%Y-%m-%d").to_string(),
+            BreakGroup::Week     => format!("{}-W{:02}",
+                                            date.iso_week().year(),
+                                            date.iso_week().week()),
+            BreakGroup::Activity =>
+                crate::db::get_activityname_for_id(db, session.id)?,
+        };
+
+        let index = match rows.iter().position(|r| r.label == label)
+        {
+            Some(index) => index,
+            None        => {
+                rows.push(BreakRow { label, ..Default::default() });
+                rows.len() - 1
+            },
+        };
+
+        match session.kind
+        {
+            Kind::Work  => rows[index].work += session.hours(),
+            Kind::Break => {
+                rows[index].breaks += session.hours();
+                rows[index].count += 1;
+            },
+        }
+    }
+
+    if group == BreakGroup::Activity
+    {
+        rows.sort_by(|a, b| a.label.cmp(&b.label));
+    }
+
+    Ok(rows)
+}
+
+/// consecutive days meeting a threshold of hours
+#[derive(Debug, Default, PartialEq)]
+pub struct Streaks {
+    pub current     : i32, // up to today (or yesterday, today isn't over)
+    pub longest     : i32,
+    pub longest_end : Option<NaiveDate>, // last day of the longest streak
+    pub longest_gap : i32, // days in between w/o meeting the threshold
+}
+
+/// retrieve streaks of days w/ at least `hours`, of one activity or (`None`)
+/// all of them in total; gaps are counted from the first such day on
+pub fn retrieve_streaks(
+    db    : &Connection,
+    hours : f64,
+    id    : Option<i32>,
+    today : NaiveDate,
+    )
+    -> Result<Streaks, Box<dyn error::Error>>
+{
+    let days : Vec<NaiveDate> = retrieve_day_totals(
+        db, NaiveDate::MIN, today, id)?
+        .into_iter()
+        .filter(|(_, total)| *total >= hours)
+        .map(|(date, _)| date)
+        .collect();
+
+    let mut streaks = Streaks::default();
+    let mut run = 0;
+
+    for (i, day) in days.iter().enumerate()
+    {
+        let gap = match i
+        {
+            0 => 0,
+            _ => (*day - days[i - 1]).num_days() as i32 - 1,
+        };
+
+        run = if i > 0 && gap == 0 { run + 1 } else { 1 };
+        streaks.longest_gap = streaks.longest_gap.max(gap);
+
+        if run > streaks.longest
+        {
+            streaks.longest = run;
+            streaks.longest_end = Some(*day);
+        }
+    }
+
+    if let Some(last) = days.last()
+    {
+        let yesterday = today - chrono::Duration::days(1);
+
+        // a streak (or gap) still going on
+        if *last >= yesterday { streaks.current = run; }
+        streaks.longest_gap = streaks.longest_gap
+            .max((yesterday - *last).num_days() as i32);
+    }
+
+    Ok(streaks)
+}
+
+/// intensity of a day for the heatmap: number of (ascending) day thresholds
+/// its hours reach; `None` for days w/o any hours
+pub fn heat_level(hours : f64, thresholds : &[f64]) -> Option<usize>
+{
+    if hours <= 0. { return None; }
+
+    Some(thresholds.iter().filter(|t| hours >= **t).count())
+}
+
+/// one line of a rollup along the client -> project -> activity hierarchy;
+/// depth 0 are clients, depth 1 their projects
+#[derive(Debug)]
+pub struct RollupRow {
+    pub depth : usize,
+    pub name  : String,
+    pub hours : Vec<f64>,
+}
+
+/// roll up per activity values (`hours` is called for every activity, active
+/// or not, returning `columns` values) to projects and clients; projects w/o
+/// client are collected under "(no client)", activities w/o project under
+/// "(no project)"; empty if no projects exist
+pub fn rollup<F>(db : &Connection, columns : usize, mut hours : F)
+    -> Result<Vec<RollupRow>, Box<dyn error::Error>>
+where
+    F : FnMut(i32) -> Result<Vec<f64>, Box<dyn error::Error>>
+{
+    let projects = crate::db::projects::get_projects(db)?;
+    let clients  = crate::db::projects::get_clients(db)?;
+    if projects.is_empty() { return Ok(Vec::new()); }
+
+    let activities = crate::db::get_all_activities(db)?;
+
+    fn add(sum : &mut [f64], values : &[f64])
+    {
+        for (s, v) in sum.iter_mut().zip(values) { *s += v; }
+    }
+
+    // per project sums (in order of `projects`), activities w/o project
+    let mut perproject = vec![vec![0.; columns]; projects.len()];
+    let mut noproject  = vec![0.; columns];
+    let mut anynoproject = false;
+
+    for activity in activities
+    {
+        let values = hours(activity.id)?;
+
+        match projects.iter().position(|p| Some(p.id) == activity.project)
+        {
+            Some(index) => add(&mut perproject[index], &values),
+            None => { add(&mut noproject, &values); anynoproject = true; },
+        }
+    }
+
+    let mut rows = Vec::new();
+
+    let groups = clients.iter()
+        .map(|c| (Some(c.id), c.name.clone()))
+        .chain(std::iter::once((None, "(no client)".to_string())));
+
+    for (client, name) in groups
+    {
+        let mut sum = vec![0.; columns];
+        let mut children = Vec::new();
+
+        for (index, project) in projects.iter().enumerate()
+        {
+            if project.client != client { continue; }
+
+            add(&mut sum, &perproject[index]);
+            children.push(RollupRow {
+                depth: 1,
+                name:  project.name.clone(),
+                hours: perproject[index].clone(),
+            });
+        }
+
+        if children.is_empty() && client.is_none() { continue; }
+
+        rows.push(RollupRow { depth: 0, name, hours: sum });
+        rows.append(&mut children);
+    }
+
+    if anynoproject
+    {
+        rows.push(RollupRow {
+            depth: 0,
+            name:  "(no project)".to_string(),
+            hours: noproject,
+        });
+    }
 
+    Ok(rows)
+}
+
+/// per tag: hours of today, this week and this month (both from yesterday
+/// back, like retrieve_total_this_week/month) and all time; ordered by tag
+pub fn tag_totals(db : &Connection, now : chrono::DateTime<Local>)
+    -> Result<Vec<RollupRow>, Box<dyn error::Error>>
+{
+    use crate::db::helpers::local_midnight;
+    use crate::db::sessions::to_utc_string;
+
+    let today = now.date_naive();
+    let yesterday = today - chrono::Duration::days(1);
+    let weekbeg = today - chrono::Duration::days(
+        today.weekday().num_days_from_monday() as i64);
+    let monthbeg = yesterday.with_day(1).unwrap();
+
+    let bounds = [today, today + chrono::Duration::days(1), weekbeg, monthbeg]
+        .into_iter()
+        .map(|date| Ok(to_utc_string(&local_midnight(date)?)))
+        .collect::<Result<Vec<String>, Box<dyn error::Error>>>()?;
+
+    // hours of the work sessions (kind ?5) w/in [beg, end); timestamps are
+    // all of the same format so MIN/MAX can compare them as text
+    let within = |beg : &str, end : &str| format!(
+        "SUM((s.kind = ?5) * MAX(0., julianday(MIN(s.ended, {})) -
+                                     julianday(MAX(s.started, {})))) * 24",
+        end, beg);
+
+    let mut stmt = db.prepare(
+        &format!("SELECT t.name, {}, {}, {},
+                     SUM((s.kind = ?5) *
+                         (julianday(s.ended) - julianday(s.started))) * 24
+                 FROM {} AS s
+                 JOIN {} AS st ON st.sid = s.sid
+                 JOIN {} AS t ON t.id = st.tag
+                 GROUP BY t.name, s.id
+                 ORDER BY t.name ASC",
+                 within("?1", "?2"), within("?3", "?1"),
+                 within("?4", "?1"),
+                 SQL_TABLEN_SES, SQL_TABLEN_STG, SQL_TABLEN_TAG))?;
+
+    let work = crate::db::sessions::Kind::Work.as_str();
+    let totals = stmt.query_map(params![bounds[0], bounds[1], bounds[2],
+                                        bounds[3], work], |row| {
+        Ok((row.get::<_, String>(0)?,
+            vec![row.get::<_, f64>(1)?, row.get::<_, f64>(2)?,
+                 row.get::<_, f64>(3)?, row.get::<_, f64>(4)?]))
+    })?;
+
+    // one row per tag and activity, summed up per tag
+    let mut rows : Vec<RollupRow> = Vec::new();
+
+    for total in totals
+    {
+        let (name, hours) = total?;
+
+        match rows.last_mut()
+        {
+            Some(row) if row.name == name =>
+            {
+                for (sum, h) in row.hours.iter_mut().zip(hours) { *sum += h; }
+            },
+            _ => rows.push(RollupRow { depth: 0, name, hours }),
+        }
+    }
+
+    Ok(rows)
+}
+
+/// counts of days and weeks of a year w/ at least `x` hours, for the
+/// thresholds defined by the user (see db::settings)
+#[derive(Debug)]
+pub struct YearCounts {
+    pub dd_thresholds : Vec<f64>,
+    pub dd_hrspls     : Vec<i32>, // per threshold: days w/ as many hrs+
+    pub dd_00_hrs     : i32,
+    pub dds_relevant  : i32,
+    pub wk_thresholds : Vec<f64>,
+    pub wk_hrspls     : Vec<i32>, // per threshold: weeks w/ as many hrs+
+    pub wk_00_hrs     : i32,
+    pub wks_relevant  : i32,
 }
 
 impl YearCounts
 {
-    pub fn new() -> YearCounts
+    pub fn new(dd_thresholds : &[f64], wk_thresholds : &[f64]) -> YearCounts
     {
         YearCounts {
-        dd_00_hrs	 : 0,
-        dd_04_hrspls : 0,
-        dd_08_hrspls : 0,
-        dd_10_hrspls : 0,
-        dd_12_hrspls : 0,
-        dd_14_hrspls : 0,
-        dds_relevant : 0,
-        wk_00_hrs	 : 0,
-        wk_10_hrspls : 0,
-        wk_20_hrspls : 0,
-        wk_30_hrspls : 0,
-        wk_40_hrspls : 0,
-        wk_50_hrspls : 0,
-        wk_60_hrspls : 0,
-        wk_70_hrspls : 0,
-        wk_80_hrspls : 0,
-        wk_90_hrspls : 0,
-        wks_relevant : 0,
+        dd_thresholds : dd_thresholds.to_vec(),
+        dd_hrspls     : vec![0; dd_thresholds.len()],
+        dd_00_hrs     : 0,
+        dds_relevant  : 0,
+        wk_thresholds : wk_thresholds.to_vec(),
+        wk_hrspls     : vec![0; wk_thresholds.len()],
+        wk_00_hrs     : 0,
+        wks_relevant  : 0,
+        }
+    }
+
+    /// count up the thresholds met by a day's hours
+    pub fn count_day(&mut self, hours : f64)
+    {
+        for (threshold, count) in
+            self.dd_thresholds.iter().zip(self.dd_hrspls.iter_mut())
+        {
+            if hours >= *threshold { *count += 1; }
         }
     }
 
+    /// count up the thresholds met by a week's hours
+    pub fn count_week(&mut self, hours : f64)
+    {
+        for (threshold, count) in
+            self.wk_thresholds.iter().zip(self.wk_hrspls.iter_mut())
+        {
+            if hours >= *threshold { *count += 1; }
+        }
+    }
+
+    /// days w/ at least `hours` (`None` if that's no threshold)
+    pub fn days_with(&self, hours : f64) -> Option<i32>
+    {
+        self.dd_thresholds.iter()
+            .position(|t| *t == hours)
+            .map(|i| self.dd_hrspls[i])
+    }
+
+    /// weeks w/ at least `hours` (`None` if that's no threshold)
+    pub fn weeks_with(&self, hours : f64) -> Option<i32>
+    {
+        self.wk_thresholds.iter()
+            .position(|t| *t == hours)
+            .map(|i| self.wk_hrspls[i])
+    }
+
     pub fn printpercentages(self)
     {
         println!("  % of days  w/  0 hrs : {:6.2}", (
                 self.dd_00_hrs as f64 / 
                 self.dds_relevant as f64) * 100.0 );
-        println!("  % of days  w/  4 hrs+: {:6.2}", (
-                self.dd_04_hrspls as f64 /
-                self.dds_relevant as f64) * 100.0 );
-        println!("  % of days  w/  8 hrs+: {:6.2}", (
-                self.dd_08_hrspls as f64 /
-                self.dds_relevant as f64) * 100.0 );
-        println!("  % of days  w/ 10 hrs+: {:6.2}", (
-                self.dd_10_hrspls as f64 /
-                self.dds_relevant as f64) * 100.0);
-        println!("  % of days  w/ 12 hrs+: {:6.2}", (
-                self.dd_12_hrspls as f64 / 
-                self.dds_relevant as f64) * 100.0);
-        /*
-        println!("  % of days  w/ 14 hrs+: {:6.2}", (
-                self.dd_14_hrspls as f64 / 
-                self.dds_relevant as f64) * 100.0);
-         */
+
+        for (threshold, count) in
+            self.dd_thresholds.iter().zip(self.dd_hrspls.iter())
+        {
+            println!("  % of days  w/ {:>2} hrs+: {:6.2}", threshold, (
+                    *count as f64 /
+                    self.dds_relevant as f64) * 100.0 );
+        }
+
         println!("  % of weeks w/  0 hrs : {:6.2}", (
                 self.wk_00_hrs as f64	  / 
                 self.wks_relevant as f64) * 100.0);
-        /*
-        println!("  % of weeks w/ 10 hrs+: {:6.2}", (
-                self.wk_10_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-         */
-        println!("  % of weeks w/ 20 hrs+: {:6.2}", (
-                self.wk_20_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-        /*
-        println!("  % of weeks w/ 30 hrs+: {:6.2}", (
-                self.wk_30_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-         */
-        println!("  % of weeks w/ 40 hrs+: {:6.2}", (
-                self.wk_40_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-        println!("  % of weeks w/ 50 hrs+: {:6.2}", (
-                self.wk_50_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-        println!("  % of weeks w/ 60 hrs+: {:6.2}", (
-                self.wk_60_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-        println!("  % of weeks w/ 70 hrs+: {:6.2}", (
-                self.wk_70_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-        println!("  % of weeks w/ 80 hrs+: {:6.2}", (
-                self.wk_80_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-        /*
-        println!("  % of weeks w/ 90 hrs+: {:6.2}", (
-                self.wk_90_hrspls as f64 / 
-                self.wks_relevant as f64) * 100.0);
-         */
 
+        for (threshold, count) in
+            self.wk_thresholds.iter().zip(self.wk_hrspls.iter())
+        {
+            println!("  % of weeks w/ {:>2} hrs+: {:6.2}", threshold, (
+                    *count as f64 /
+                    self.wks_relevant as f64) * 100.0);
+        }
     }
 }
 
+/// counts of the same thresholds only; used to sum up years
 impl<'a> Add for &'a YearCounts
 {
     type Output = YearCounts;
 
     fn add(self, other: &'a YearCounts) -> YearCounts
     {
+        assert_eq!(self.dd_thresholds, other.dd_thresholds);
+        assert_eq!(self.wk_thresholds, other.wk_thresholds);
+
+        let sum = |a : &[i32], b : &[i32]| {
+            a.iter().zip(b.iter()).map(|(a, b)| a + b).collect()
+        };
+
         YearCounts {
+            dd_thresholds: self.dd_thresholds.clone(),
+            dd_hrspls:	  sum(&self.dd_hrspls, &other.dd_hrspls),
             dd_00_hrs: 	  self.dd_00_hrs    + other.dd_00_hrs,
-            dd_04_hrspls: self.dd_04_hrspls + other.dd_04_hrspls,
-            dd_08_hrspls: self.dd_08_hrspls + other.dd_08_hrspls,
-            dd_10_hrspls: self.dd_10_hrspls + other.dd_10_hrspls,
-            dd_12_hrspls: self.dd_12_hrspls + other.dd_12_hrspls,
-            dd_14_hrspls: self.dd_14_hrspls + other.dd_14_hrspls,
             dds_relevant: self.dds_relevant + other.dds_relevant,
+            wk_thresholds: self.wk_thresholds.clone(),
+            wk_hrspls:	  sum(&self.wk_hrspls, &other.wk_hrspls),
             wk_00_hrs	: self.wk_00_hrs	+ other.wk_00_hrs,
-            wk_10_hrspls: self.wk_10_hrspls	+ other.wk_10_hrspls,
-            wk_20_hrspls: self.wk_20_hrspls	+ other.wk_20_hrspls,
-            wk_30_hrspls: self.wk_30_hrspls	+ other.wk_30_hrspls,
-            wk_40_hrspls: self.wk_40_hrspls	+ other.wk_40_hrspls,
-            wk_50_hrspls: self.wk_50_hrspls	+ other.wk_50_hrspls,
-            wk_60_hrspls: self.wk_60_hrspls	+ other.wk_60_hrspls,
-            wk_70_hrspls: self.wk_70_hrspls	+ other.wk_70_hrspls,
-            wk_80_hrspls: self.wk_80_hrspls	+ other.wk_80_hrspls,
-            wk_90_hrspls: self.wk_90_hrspls	+ other.wk_90_hrspls,
             wks_relevant: self.wks_relevant + other.wks_relevant,
         }
 
@@ -499,6 +1094,20 @@ pub struct HistoryRowISO8601 {
     pub year	: i32,
 }
 
+/// SQL condition restricting history rows to the given activities (all if
+/// `None`)
+fn activities_condition(activities : Option<&[i32]>) -> String
+{
+    match activities
+    {
+        Some(ids) => format!("id IN ({})", ids.iter()
+                             .map(|id| id.to_string())
+                             .collect::<Vec<_>>()
+                             .join(", ")),
+        None => "1".to_string(),
+    }
+}
+
 pub fn retrieve_percentages_for_year(
     db    : &Connection,
     year  : i32,
@@ -506,11 +1115,28 @@ pub fn retrieve_percentages_for_year(
     )
     -> Result<YearCounts, Box<dyn error::Error>>
 {
-    let mut stats = YearCounts::new();
+    retrieve_percentages_for_year_of(db, year, today, None)
+}
+
+/// like retrieve_percentages_for_year(), but only counting the hours of the
+/// given activities (all if `None`); days and weeks are counted from the
+/// first entry of these activities on
+pub fn retrieve_percentages_for_year_of(
+    db         : &Connection,
+    year       : i32,
+    today      : chrono::DateTime<Local>,
+    activities : Option<&[i32]>,
+    )
+    -> Result<YearCounts, Box<dyn error::Error>>
+{
+    let mut stats = YearCounts::new(
+        &crate::db::settings::day_thresholds(db)?,
+        &crate::db::settings::week_thresholds(db)?);
+    let cond = activities_condition(activities);
 
     // retrieve year,month,day of first entry (important to adjust statistics)
 
-    let firstentry = firstentry_datetime(&db)?;
+    let firstentry = firstentry_datetime_of(db, activities)?;
     let firstyy = firstentry.year();
     if year < firstyy { return Err("Out of scope".into()); }
 
@@ -525,8 +1151,8 @@ pub fn retrieve_percentages_for_year(
      */
 
     let mut stmt = db.prepare(
-        &format!("SELECT * FROM {} WHERE year=?1 ORDER BY date ASC",
-                 SQL_TABLEN_HIS)
+        &format!("SELECT * FROM {} WHERE year=?1 AND {} ORDER BY date ASC",
+                 SQL_TABLEN_HIS, cond)
         )?;
 
     let data_iter = stmt.query_map(
@@ -558,11 +1184,6 @@ pub fn retrieve_percentages_for_year(
      */
 
     let mut days_with_entries = 0;
-    let mut num_days_04_hours = 0;
-    let mut num_days_08_hours = 0;
-    let mut num_days_10_hours = 0;
-    let mut num_days_12_hours = 0;
-    let mut num_days_14_hours = 0;
 
 	let mut iter = historyvec.iter().peekable();
 
@@ -604,21 +1225,12 @@ pub fn retrieve_percentages_for_year(
 
         // based on this value count up criteria counters
 
-        if hoursum >= 04. { num_days_04_hours += 1; }
-        if hoursum >= 08. { num_days_08_hours += 1; }
-        if hoursum >= 10. { num_days_10_hours += 1; }
-        if hoursum >= 12. { num_days_12_hours += 1; }
-        if hoursum >= 14. { num_days_14_hours += 1; }
+        stats.count_day(hoursum);
     }
 
     let num_days_00_hours = (relevantddcount - days_with_entries).abs();
 
     stats.dd_00_hrs    = num_days_00_hours;
-    stats.dd_04_hrspls = num_days_04_hours;
-    stats.dd_08_hrspls = num_days_08_hours;
-    stats.dd_10_hrspls = num_days_10_hours;
-    stats.dd_12_hrspls = num_days_12_hours;
-    stats.dd_14_hrspls = num_days_14_hours;
     stats.dds_relevant = relevantddcount;
 
     /*
@@ -630,8 +1242,8 @@ pub fn retrieve_percentages_for_year(
      */
 
     let mut stmt = db.prepare(
-        &format!("SELECT * FROM {} WHERE isoweekyear=?1 ORDER BY date ASC",
-                 SQL_TABLEN_HIS)
+        &format!("SELECT * FROM {} WHERE isoweekyear=?1 AND {}
+                 ORDER BY date ASC", SQL_TABLEN_HIS, cond)
         )?;
 
     let data_iter = stmt.query_map(
@@ -666,15 +1278,6 @@ pub fn retrieve_percentages_for_year(
      */
 
     let mut num_weeks_taken_into_account = 0;
-    let mut num_weeks_10_hours = 0;
-    let mut num_weeks_20_hours = 0;
-    let mut num_weeks_30_hours = 0;
-    let mut num_weeks_40_hours = 0;
-    let mut num_weeks_50_hours = 0;
-    let mut num_weeks_60_hours = 0;
-    let mut num_weeks_70_hours = 0;
-    let mut num_weeks_80_hours = 0;
-    let mut num_weeks_90_hours = 0;
 
 	let mut iter = historyvec.iter().peekable();
     
@@ -742,15 +1345,7 @@ pub fn retrieve_percentages_for_year(
 
         // based on this value count up criteria counters
 
-        if hoursum >= 10. { num_weeks_10_hours += 1; };
-        if hoursum >= 20. { num_weeks_20_hours += 1; };
-        if hoursum >= 30. { num_weeks_30_hours += 1; };
-        if hoursum >= 40. { num_weeks_40_hours += 1; };
-        if hoursum >= 50. { num_weeks_50_hours += 1; };
-        if hoursum >= 60. { num_weeks_60_hours += 1; };
-        if hoursum >= 70. { num_weeks_70_hours += 1; };
-        if hoursum >= 80. { num_weeks_80_hours += 1; };
-        if hoursum >= 90. { num_weeks_90_hours += 1; };
+        stats.count_week(hoursum);
     }
 
     /*
@@ -768,15 +1363,6 @@ pub fn retrieve_percentages_for_year(
      */
 
     stats.wk_00_hrs    = num_weeks_00_hours;
-    stats.wk_10_hrspls = num_weeks_10_hours;
-    stats.wk_20_hrspls = num_weeks_20_hours;
-    stats.wk_30_hrspls = num_weeks_30_hours;
-    stats.wk_40_hrspls = num_weeks_40_hours;
-    stats.wk_50_hrspls = num_weeks_50_hours;
-    stats.wk_60_hrspls = num_weeks_60_hours;
-    stats.wk_70_hrspls = num_weeks_70_hours;
-    stats.wk_80_hrspls = num_weeks_80_hours;
-    stats.wk_90_hrspls = num_weeks_90_hours;
     stats.wks_relevant = relevantwkcount;
 
     Ok(stats)
@@ -872,6 +1458,12 @@ mod tests
         test::populate_db_w_data(&mut testdb);
         let epsilon = 0.001;
 
+        // every threshold of the test data, not just the default ones
+        use crate::db::settings::*;
+        set_thresholds(&testdb, KEY_DAY_THRESHOLDS, "4 8 10 12 14").unwrap();
+        set_thresholds(&testdb, KEY_WEEK_THRESHOLDS,
+                       "10 20 30 40 50 60 70 80 90").unwrap();
+
         let now  = chrono::Local.with_ymd_and_hms(2024,03,20,0,0,0).unwrap();
 
         /* 2024-01: 31 days
@@ -913,11 +1505,11 @@ mod tests
         // dd_00_hrs: days from 2024-03-05 till 2024-03-19 (both included)
         // (since chrono::Local passed to rpfy is that date)
         assert_eq!(yc24.dd_00_hrs   , 15);
-        assert_eq!(yc24.dd_04_hrspls, 64 - 1);
-        assert_eq!(yc24.dd_08_hrspls, 64 - 1 - 30 - 1);
-        assert_eq!(yc24.dd_10_hrspls, 64 - 1 - 30 - 1);
-        assert_eq!(yc24.dd_12_hrspls, 64 - 1 - 30 - 1 - 28);
-        assert_eq!(yc24.dd_14_hrspls, 64 - 1 - 30 - 1 - 28);
+        assert_eq!(yc24.days_with(4.), Some(64 - 1));
+        assert_eq!(yc24.days_with(8.), Some(64 - 1 - 30 - 1));
+        assert_eq!(yc24.days_with(10.), Some(64 - 1 - 30 - 1));
+        assert_eq!(yc24.days_with(12.), Some(64 - 1 - 30 - 1 - 28));
+        assert_eq!(yc24.days_with(14.), Some(64 - 1 - 30 - 1 - 28));
         assert_eq!(yc24.dds_relevant, 64 + 15);
 
         /*
@@ -932,21 +1524,22 @@ mod tests
          */
         assert_eq!(yc24.wk_00_hrs,    1);
         // all relevant weeks minus the one w/ no entries
-        assert_eq!(yc24.wk_10_hrspls, (11 - 1));
+        assert_eq!(yc24.weeks_with(10.), Some(11 - 1));
         // minus last week w/ only one entry (2024-03-04 is Monday w/ 14 hours)
-        assert_eq!(yc24.wk_20_hrspls, (11 - 1 - 1));
-        assert_eq!(yc24.wk_30_hrspls, (11 - 1 - 1));
+        assert_eq!(yc24.weeks_with(20.), Some(11 - 1 - 1));
+        assert_eq!(yc24.weeks_with(30.), Some(11 - 1 - 1));
         // minus first january week
-        assert_eq!(yc24.wk_40_hrspls, 11 - 1 - 1 - 1);
+        assert_eq!(yc24.weeks_with(40.), Some(11 - 1 - 1 - 1));
         // minus complete full january weeks
-        assert_eq!(yc24.wk_50_hrspls, 11 - 1 - 1 - 1 - 3);
+        assert_eq!(yc24.weeks_with(50.), Some(11 - 1 - 1 - 1 - 3));
         // minus januar/february spill over week
-        assert_eq!(yc24.wk_60_hrspls, 11 - 1 - 1 - 1 - 3 - 1);
-        assert_eq!(yc24.wk_70_hrspls, 11 - 1 - 1 - 1 - 3 - 1);
+        assert_eq!(yc24.weeks_with(60.), Some(11 - 1 - 1 - 1 - 3 - 1));
+        assert_eq!(yc24.weeks_with(70.), Some(11 - 1 - 1 - 1 - 3 - 1));
         // minutes all full february weeks
-        assert_eq!(yc24.wk_80_hrspls, 11 - 1 - 1 - 1 - 3 - 1 - 3);
+        assert_eq!(yc24.weeks_with(80.), Some(11 - 1 - 1 - 1 - 3 - 1 - 3));
         // minus february/march spillover week, should be at 0 now
-        assert_eq!(yc24.wk_90_hrspls, 11 - 1 - 1 - 1 - 3 - 1 - 3 - 1);
+        assert_eq!(yc24.weeks_with(90.),
+                   Some(11 - 1 - 1 - 1 - 3 - 1 - 3 - 1));
 
         /*
          * 2023
@@ -965,11 +1558,11 @@ mod tests
          */
 
         assert_eq!(yc23.dd_00_hrs   , 0);
-        assert_eq!(yc23.dd_04_hrspls, 0);
-        assert_eq!(yc23.dd_08_hrspls, 0);
-        assert_eq!(yc23.dd_10_hrspls, 0);
-        assert_eq!(yc23.dd_12_hrspls, 0);
-        assert_eq!(yc23.dd_14_hrspls, 0);
+        assert_eq!(yc23.days_with(4.), Some(0));
+        assert_eq!(yc23.days_with(8.), Some(0));
+        assert_eq!(yc23.days_with(10.), Some(0));
+        assert_eq!(yc23.days_with(12.), Some(0));
+        assert_eq!(yc23.days_with(14.), Some(0));
         assert_eq!(yc23.dds_relevant, 20);
 
         /*
@@ -980,15 +1573,15 @@ mod tests
         assert_eq!(yc23.wks_relevant, 2); // used in subsequent calculations
 
         assert_eq!(yc23.wk_00_hrs,    0);
-        assert_eq!(yc23.wk_10_hrspls, 2);
-        assert_eq!(yc23.wk_20_hrspls, 0);
-        assert_eq!(yc23.wk_30_hrspls, 0);
-        assert_eq!(yc23.wk_40_hrspls, 0);
-        assert_eq!(yc23.wk_50_hrspls, 0);
-        assert_eq!(yc23.wk_60_hrspls, 0);
-        assert_eq!(yc23.wk_70_hrspls, 0);
-        assert_eq!(yc23.wk_80_hrspls, 0);
-        assert_eq!(yc23.wk_90_hrspls, 0);
+        assert_eq!(yc23.weeks_with(10.), Some(2));
+        assert_eq!(yc23.weeks_with(20.), Some(0));
+        assert_eq!(yc23.weeks_with(30.), Some(0));
+        assert_eq!(yc23.weeks_with(40.), Some(0));
+        assert_eq!(yc23.weeks_with(50.), Some(0));
+        assert_eq!(yc23.weeks_with(60.), Some(0));
+        assert_eq!(yc23.weeks_with(70.), Some(0));
+        assert_eq!(yc23.weeks_with(80.), Some(0));
+        assert_eq!(yc23.weeks_with(90.), Some(0));
 
         /*
          * TEST EXTRA: PROPER RELEVANT WEEK / DAYS HANDLING
@@ -1036,11 +1629,406 @@ mod tests
 
         assert_eq!(s.dds_relevant, 17); // actual days in calendar year
         assert_eq!(s.dd_00_hrs,    17); // actual calendar year!
-        assert_eq!(s.dd_04_hrspls, 00); // actual calendar year!
+        assert_eq!(s.days_with(4.), Some(0)); // actual calendar year!
 
         /* subsequent test removed, I think we have enough as is
          * and there isn't much more to test really
          */
     }
 
+    #[test]
+    fn percentages_per_activity()
+    {
+        let mut testdb = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut testdb);
+        test::populate_db_w_activities(&mut testdb);
+        test::populate_db_w_data(&mut testdb);
+
+        let now = chrono::Local.with_ymd_and_hms(2024,03,20,0,0,0).unwrap();
+        use crate::db::settings::*;
+        set_thresholds(&testdb, KEY_WEEK_THRESHOLDS, "10 20").unwrap();
+
+        // all activities selected: same as unfiltered
+        let all = retrieve_percentages_for_year(&testdb, 2024, now).unwrap();
+        let sel = retrieve_percentages_for_year_of(
+            &testdb, 2024, now, Some(&[1, 2, 3, 4])).unwrap();
+        assert_eq!(all.dd_hrspls, sel.dd_hrspls);
+        assert_eq!(all.wk_hrspls, sel.wk_hrspls);
+        assert_eq!(all.wks_relevant, sel.wks_relevant);
+
+        // a quarter of the hours: 1.5 (Jan), 2.5 (Feb), 3.5 (Mar) a day
+        let one = retrieve_percentages_for_year_of(
+            &testdb, 2024, now, Some(&[4])).unwrap();
+        assert_eq!(one.dds_relevant, all.dds_relevant);
+        assert_eq!(one.days_with(4.), Some(0));
+        // first week of the year 6 days only (Jan 1st is Dec 31st's work)
+        // last week only Mar 4th, and one week w/o entries
+        assert_eq!(one.weeks_with(10.), Some(11 - 1 - 1 - 1));
+        // only the February/March spillover week (4 * 2.5 + 3 * 3.5)
+        assert_eq!(one.weeks_with(20.), Some(1));
+
+        assert!(retrieve_percentages_for_year_of(
+                &testdb, 2024, now, Some(&[])).is_err());
+    }
+
+    #[test]
+    fn range_totals()
+    {
+        let mut testdb = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut testdb);
+        test::populate_db_w_activities(&mut testdb);
+        test::populate_db_w_data(&mut testdb);
+        let epsilon = 0.001;
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // 7 January days (1.5), 10 February days (2.5)
+        let total = retrieve_total_range(
+            &testdb, date(2024, 1, 10), date(2024, 1, 16), 1).unwrap();
+        assert!((total - 7. * 1.5).abs() <= epsilon);
+        let total = retrieve_total_range(
+            &testdb, date(2024, 2, 10), date(2024, 2, 19), 4).unwrap();
+        assert!((total - 10. * 2.5).abs() <= epsilon);
+        let none = retrieve_total_range(
+            &testdb, date(2022, 1, 1), date(2022, 12, 31), 1).unwrap();
+        assert!(none.abs() <= epsilon);
+
+        // relevant days are clipped to first entry and today
+        let first = firstentry_datetime(&testdb).unwrap();
+        let today = chrono::Local.with_ymd_and_hms(2024,03,20,0,0,0).unwrap();
+        assert_eq!(relevantddcount_range(
+                &first, date(2023, 12, 1), date(2023, 12, 31), &today), 20);
+        assert_eq!(relevantddcount_range(
+                &first, date(2024, 3, 1), date(2024, 3, 31), &today), 20);
+        assert_eq!(relevantddcount_range(
+                &first, date(2024, 4, 1), date(2024, 4, 30), &today), 0);
+    }
+
+    #[test]
+    fn series_per_period()
+    {
+        let mut testdb = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut testdb);
+        test::populate_db_w_activities(&mut testdb);
+        test::populate_db_w_data(&mut testdb);
+        let epsilon = 0.001;
+
+        let months = retrieve_series(
+            &testdb, Period::Month, None, &[1, 2, 3, 4]).unwrap();
+        let labels : Vec<&str> = months.iter()
+            .map(|r| r.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["2023-12", "2024-01", "2024-02", "2024-03"]);
+
+        // all hours accounted for (see test.rs)
+        let total : f64 = months.iter().flat_map(|r| r.hours.iter()).sum();
+        assert!((total - 562.).abs() <= epsilon);
+
+        // ISO weeks of 2024: W01 to the one of March 4th (W10)
+        let weeks = retrieve_series(
+            &testdb, Period::Week, Some(2024), &[2]).unwrap();
+        assert_eq!(weeks.len(), 10);
+        assert_eq!(weeks[0].label, "2024-W01");
+        assert!((weeks[2].hours[0] - 7. * 1.5).abs() <= epsilon);
+
+        // weeks w/o entries are listed anyway, turning over the year
+        crate::db::enter_into_db(
+            &testdb,
+            &chrono::Local.with_ymd_and_hms(2025, 1, 8, 9, 0, 0).unwrap(),
+            &chrono::Local.with_ymd_and_hms(2025, 1, 8, 10, 0, 0).unwrap(),
+            2).unwrap();
+        let weeks = retrieve_series(
+            &testdb, Period::Week, None, &[2]).unwrap();
+        assert_eq!(weeks.last().unwrap().label, "2025-W02");
+        assert_eq!(weeks.len(), 3 + 52 + 2); // from 2023-W50 on
+        assert!(retrieve_series(&testdb, Period::Month, Some(2022), &[2])
+                .unwrap().is_empty());
+    }
+
+    #[test]
+    fn heatmap_levels()
+    {
+        let mut testdb = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut testdb);
+        test::populate_db_w_activities(&mut testdb);
+        test::populate_db_w_data(&mut testdb);
+        let epsilon = 0.001;
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        let days = retrieve_day_totals(
+            &testdb, date(2024, 1, 10), date(2024, 3, 31), None).unwrap();
+        assert_eq!(days.first().unwrap().0, date(2024, 1, 10));
+        assert_eq!(days.last().unwrap().0, date(2024, 3, 4));
+        assert!((days[0].1 - 6.).abs() <= epsilon);
+
+        let thresholds = [4., 8., 10., 12.];
+        assert_eq!(heat_level(0., &thresholds), None);
+        assert_eq!(heat_level(2., &thresholds), Some(0));
+        assert_eq!(heat_level(8., &thresholds), Some(2));
+        assert_eq!(heat_level(14., &thresholds), Some(4));
+    }
+
+    #[test]
+    fn weekdays()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        test::populate_db_w_data(&mut db);
+        let epsilon = 0.001;
+
+        // 2023-12-12 (Tue) until 2024-03-10 (Sun): 12 Mondays, 13 of others
+        let today = NaiveDate::from_ymd_opt(2024, 03, 10).unwrap();
+        let rows = retrieve_weekdays(&db, &[1, 2, 3, 4], today).unwrap();
+        assert_eq!(rows.len(), 7);
+        assert_eq!(rows[0].weekday, Weekday::Mon);
+        assert_eq!(rows[0].days, 12);
+        assert!(rows[1..].iter().all(|r| r.days == 13));
+
+        let total : f64 = rows.iter()
+            .map(|r| r.hours.iter().sum::<f64>())
+            .sum();
+        assert!((total - 562.).abs() <= epsilon);
+
+        // only the activities asked for
+        let rows = retrieve_weekdays(&db, &[2], today).unwrap();
+        let total : f64 = rows.iter().map(|r| r.hours[0]).sum();
+        assert!((total - 562. / 4.).abs() <= epsilon);
+
+        // nothing before the first entry
+        let today = NaiveDate::from_ymd_opt(2023, 12, 1).unwrap();
+        let rows = retrieve_weekdays(&db, &[1], today).unwrap();
+        assert!(rows.iter().all(|r| r.days == 0 && r.hours[0] == 0.));
+    }
+
+    #[test]
+    fn hours_of_day()
+    {
+        use crate::db::sessions::{self, Kind, Source};
+
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        let at = |dd, hh, mm| Local.with_ymd_and_hms(2024, 01, dd, hh, mm, 0)
+            .unwrap();
+        sessions::enter(&db, &at(10, 9, 30), &at(10, 11, 15), 1,
+                        Kind::Work, Source::Timer).unwrap();
+        sessions::enter(&db, &at(10, 11, 15), &at(10, 11, 45), 1,
+                        Kind::Break, Source::Timer).unwrap();
+        sessions::enter(&db, &at(10, 23, 30), &at(11, 0, 30), 2,
+                        Kind::Work, Source::Import).unwrap();
+        // manual entries begin at midnight, they don't count
+        sessions::enter(&db, &at(11, 0, 0), &at(11, 2, 0), 1,
+                        Kind::Work, Source::Manual).unwrap();
+
+        let from = NaiveDate::from_ymd_opt(2024, 01, 01).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 01, 31).unwrap();
+        let buckets = retrieve_hours_of_day(&db, &[1, 2], from, to).unwrap();
+
+        assert!((buckets[0][9] - 0.5).abs() <= epsilon);
+        assert!((buckets[0][10] - 1.).abs() <= epsilon);
+        assert!((buckets[0][11] - 0.25).abs() <= epsilon);
+        assert!((buckets[0].iter().sum::<f64>() - 1.75).abs() <= epsilon);
+        assert!((buckets[1][23] - 0.5).abs() <= epsilon);
+        assert!((buckets[1][0] - 0.5).abs() <= epsilon);
+
+        // only the activities asked for
+        let buckets = retrieve_hours_of_day(&db, &[2], from, to).unwrap();
+        assert!((buckets[0].iter().sum::<f64>() - 1.).abs() <= epsilon);
+    }
+
+    #[test]
+    fn breaks()
+    {
+        use crate::db::sessions::{self, Kind, Source};
+
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        let at = |dd, hh, mm| Local.with_ymd_and_hms(2024, 01, dd, hh, mm, 0)
+            .unwrap();
+        let phases = [((10, 9, 0), (10, 11, 0), 1, Kind::Work),
+                      ((10, 11, 0), (10, 11, 15), 1, Kind::Break),
+                      ((10, 11, 15), (10, 12, 15), 2, Kind::Work),
+                      ((10, 12, 15), (10, 12, 45), 2, Kind::Break),
+                      ((11, 9, 0), (11, 10, 0), 2, Kind::Work)];
+        for (beg, end, id, kind) in phases
+        {
+            sessions::enter(&db, &at(beg.0, beg.1, beg.2),
+                            &at(end.0, end.1, end.2), id, kind,
+                            Source::Timer).unwrap();
+        }
+        // manual entries have no breaks, they don't count
+        sessions::enter(&db, &at(11, 0, 0), &at(11, 8, 0), 1,
+                        Kind::Work, Source::Manual).unwrap();
+
+        let from = NaiveDate::from_ymd_opt(2024, 01, 01).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 01, 31).unwrap();
+
+        let days = retrieve_breaks(&db, from, to, BreakGroup::Day).unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].label, "2024-01-10");
+        assert!((days[0].work - 3.).abs() <= epsilon);
+        assert_eq!(days[0].count, 2);
+        assert!((days[0].ratio() - 0.25).abs() <= epsilon);
+        assert!((days[0].avg_minutes() - 22.5).abs() <= epsilon);
+        assert_eq!(days[1].count, 0);
+        assert!((days[1].avg_minutes()).abs() <= epsilon);
+
+        let weeks = retrieve_breaks(&db, from, to, BreakGroup::Week).unwrap();
+        assert_eq!(weeks.len(), 1);
+        assert_eq!(weeks[0].label, "2024-W02");
+        assert!((weeks[0].work - 4.).abs() <= epsilon);
+
+        let acts = retrieve_breaks(&db, from, to, BreakGroup::Activity)
+            .unwrap();
+        assert_eq!(acts[0].label, "A");
+        assert!((acts[0].ratio() - 0.125).abs() <= epsilon);
+        assert!((acts[1].ratio() - 0.25).abs() <= epsilon);
+    }
+
+    #[test]
+    fn streaks()
+    {
+        let mut testdb = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut testdb);
+        test::populate_db_w_activities(&mut testdb);
+        test::populate_db_w_data(&mut testdb);
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        // 4 hrs+ in total from 2024-01-02 (Jan 1st only 1 hr) to 2024-03-04
+        let s = retrieve_streaks(&testdb, 4., None, date(2024, 3, 20))
+            .unwrap();
+        assert_eq!(s, Streaks {
+            current     : 0,
+            longest     : 30 + 29 + 4,
+            longest_end : Some(date(2024, 3, 4)),
+            longest_gap : 15, // Mar 5th to 19th, today isn't over
+        });
+
+        // 1 hr+ on activity 1 from 2024-01-01 on, but Feb 1st (0.5 hrs, the
+        // next session begins on Feb 2nd 01:00)
+        let s = retrieve_streaks(&testdb, 1., Some(1), date(2024, 3, 5))
+            .unwrap();
+        assert_eq!((s.current, s.longest, s.longest_gap), (28 + 4, 28 + 4, 1));
+
+        let s = retrieve_streaks(&testdb, 24., None, date(2024, 3, 5))
+            .unwrap();
+        assert_eq!(s, Streaks::default());
+    }
+
+    #[test]
+    fn rollup_per_client_project()
+    {
+        let mut testdb = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut testdb);
+        test::populate_db_w_activities(&mut testdb);
+        test::populate_db_w_data(&mut testdb);
+        let epsilon = 0.001;
+
+        let alltime = |id| Ok(vec![retrieve_total_alltime(&testdb, id)?]);
+
+        // nothing to roll up w/o projects
+        assert!(rollup(&testdb, 1, alltime).unwrap().is_empty());
+
+        use crate::db::projects::*;
+        let acme = add_client(&testdb, "ACME").unwrap();
+        let web  = add_project(&testdb, "Website", Some(acme)).unwrap();
+        let app  = add_project(&testdb, "App", Some(acme)).unwrap();
+        let own  = add_project(&testdb, "Internal", None).unwrap();
+        assign(&testdb, 1, Some(web)).unwrap();
+        assign(&testdb, 2, Some(web)).unwrap();
+        assign(&testdb, 3, Some(own)).unwrap();
+        crate::db::deactivate_activity(&testdb, 2).unwrap();
+
+        let rows = rollup(&testdb, 1, alltime).unwrap();
+        let names : Vec<(usize, &str)> = rows.iter()
+            .map(|r| (r.depth, r.name.as_str()))
+            .collect();
+        assert_eq!(names, vec![(0, "ACME"), (1, "App"), (1, "Website"),
+                               (0, "(no client)"), (1, "Internal"),
+                               (0, "(no project)")]);
+
+        // every activity has the same total (see test.rs)
+        let each = 562. / 4.;
+        assert!((rows[0].hours[0] - 2. * each).abs() <= epsilon);
+        assert!((rows[1].hours[0] - 0.).abs() <= epsilon);
+        assert!((rows[2].hours[0] - 2. * each).abs() <= epsilon);
+        assert!((rows[3].hours[0] - each).abs() <= epsilon);
+        assert!((rows[5].hours[0] - each).abs() <= epsilon);
+
+        // yearly totals
+        let rows = rollup(&testdb, 1, |id| {
+            Ok(vec![retrieve_total_year(&testdb, 2023, id)?])
+        }).unwrap();
+        assert!((rows[0].hours[0] - 2. * 20. * 0.5).abs() <= epsilon);
+    }
+
+    #[test]
+    fn totals_per_tag()
+    {
+        use crate::db::sessions::{self, Kind, Source};
+        use crate::db::tags;
+
+        let mut testdb = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut testdb);
+        test::populate_db_w_activities(&mut testdb);
+        let epsilon = 0.001;
+
+        // a wednesday; the week begins on 2024-03-04, the month on 03-01
+        let now = Local.with_ymd_and_hms(2024, 03, 06, 12, 0, 0).unwrap();
+        let enter = |beg : chrono::DateTime<Local>, hours, id, kind, tag| {
+            let end = beg + chrono::Duration::hours(hours);
+            let sid = sessions::enter(&testdb, &beg, &end, id, kind,
+                                      Source::Timer).unwrap();
+            tags::add(&testdb, sid, &tags::parse(tag)).unwrap();
+        };
+
+        let at = |d, h| Local.with_ymd_and_hms(2024, 03, d, h, 0, 0).unwrap();
+        enter(at(6, 8), 2, 1, Kind::Work, "meeting review");
+        enter(at(6, 10), 1, 2, Kind::Break, "meeting");
+        enter(at(5, 22), 4, 2, Kind::Work, "meeting"); // across midnight
+        enter(at(2, 9), 3, 3, Kind::Work, "meeting");
+        enter(at(1, 9), 5, 1, Kind::Work, "review");
+        enter(Local.with_ymd_and_hms(2024, 02, 20, 9, 0, 0).unwrap(), 6, 4,
+              Kind::Work, "review");
+
+        let rows = tag_totals(&testdb, now).unwrap();
+        let names : Vec<&str> = rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["meeting", "review"]);
+
+        for (row, expected) in rows.iter().zip([[4., 2., 5., 9.],
+                                                [2., 0., 5., 13.]])
+        {
+            for (hours, exp) in row.hours.iter().zip(expected)
+            {
+                assert!((hours - exp).abs() <= epsilon);
+            }
+        }
+
+        // same as the history based totals of a db filtered to the tag
+        for row in &rows
+        {
+            let copy = tags::filtered(&testdb, &row.name).unwrap();
+            let mut hours = vec![0.; 4];
+            for id in 1..=4
+            {
+                hours[0] += retrieve_total_today(&copy, now, id).unwrap();
+                hours[1] += retrieve_total_this_week(&copy, now, id).unwrap();
+                hours[2] += retrieve_total_this_month(&copy, now, id).unwrap();
+                hours[3] += retrieve_total_alltime(&copy, id).unwrap();
+            }
+            for (a, b) in row.hours.iter().zip(hours)
+            {
+                assert!((a - b).abs() <= epsilon);
+            }
+        }
+    }
+
 }
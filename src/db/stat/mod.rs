@@ -5,27 +5,30 @@ pub mod helpers;
 
 use std::error;
 use std::io;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 
-use chrono::Datelike;
+use chrono::{Datelike, NaiveDate, Weekday};
 use rusqlite::Connection;
 use helpers::*;
 
 pub fn printstats(db : &Connection)
     -> Result<(), Box<dyn error::Error>>
+{
+    printstats_detail(db, None)
+}
+
+/// prints stats; detailed statistics per activity are printed depending on
+/// `detailed`, or if `None` the user is prompted for them
+pub fn printstats_detail(db : &Connection, detailed : Option<bool>)
+    -> Result<(), Box<dyn error::Error>>
 {
     let now = chrono::Local::now();
 
-    // retrieve highest active id (we'll iterate up to max ids)
+    // retrieve active activities (we'll iterate over them)
 
-    let id_highestactive : i32 = db.query_row(
-        &format!(
-            "SELECT id FROM {} WHERE id > 0 
-            ORDER BY id DESC LIMIT 1",
-            crate::db::queries::SQL_TABLEN_ACT), (),
-            |row| row.get(0)).unwrap_or(0);
+    let activities = crate::db::get_activities(db, true)?;
 
-    if id_highestactive == 0
+    if activities.is_empty()
     {
         println!("No activities are configured");
         return Ok(());
@@ -53,9 +56,9 @@ pub fn printstats(db : &Connection)
 
     // iterate over activities, use helper functions to retrieve values
 
-    for i in 0..id_highestactive
+    for activity in activities.iter()
     {
-        let id = i + 1;
+        let id = activity.id;
         week_tot.push(retrieve_total_this_week(db, now, id).unwrap());
         todaytot.push(retrieve_total_today(db, now, id).unwrap());
         last5ddtot.push(retrieve_total_last_x_days(db, 5, now, id).unwrap());
@@ -63,13 +66,7 @@ pub fn printstats(db : &Connection)
         last6wktot.push(retrieve_total_last_x_weeks(db, 6, now, id).unwrap());
         monthtot.push(retrieve_total_this_month(db, now, id).unwrap());
 
-        activitynames.push(
-            db.query_row(
-                &format!("SELECT name FROM {} WHERE id = ?",
-                         crate::db::queries::SQL_TABLEN_ACT),
-                         rusqlite::params![id], 
-                         |row| row.get(0)).unwrap()
-            );
+        activitynames.push(activity.name.clone());
     }
 
     // compute the total and avg values (for all activites in our vectors)
@@ -145,22 +142,55 @@ pub fn printstats(db : &Connection)
     println!("This month avg/day:  {:6.2}", monthtotalavg);
     println!("---------------------------------------------------------------");
 
-    print!("Print detailed statistics per activity? (y/n): ");
-    io::stdout().flush().unwrap();
-   	let mut choice : String = Default::default();
+    // streaks of days w/ enough hours in total
+    let streakhours = crate::db::settings::streak_hours(db)?;
+    let streaks = retrieve_streaks(db, streakhours, None, now.date_naive())?;
+    println!("Streak of days w/ {} hrs+", streakhours);
+    printstreaks(&streaks);
+    println!("---------------------------------------------------------------");
 
-    loop
+    // progress towards goals (if any are set)
+    printgoals(&crate::db::goals::progress(db, now.date_naive())?);
+
+    // totals rolled up per client and project (if any are configured)
+    let rows = rollup(db, 4, |id| {
+        Ok(vec![retrieve_total_today(db, now, id)?,
+                retrieve_total_this_week(db, now, id)?,
+                retrieve_total_this_month(db, now, id)?,
+                retrieve_total_alltime(db, id)?])
+    })?;
+    printrollup(&rows, "Client / project",
+                &["Today", "Week", "Month", "All time"]);
+
+    // totals per tag (if any are in use)
+    printtags(db)?;
+
+    match detailed
     {
-    	choice.clear();
-        io::stdin().read_line(&mut choice).expect("Failed to read line");
-        if ["n", "N"].contains(&choice.trim())
-        { 
-            println!(" - - - ");
-            return Ok(());
-        };
-        if ["y", "Y"].contains(&choice.trim()) { break; };
+        Some(true)  => (),
+        Some(false) => return Ok(()),
+        None        => {
+            print!("Print detailed statistics per activity? (y/n): ");
+            io::stdout().flush().unwrap();
+            let mut choice : String = Default::default();
+
+            loop
+            {
+                choice.clear();
+                io::stdin().read_line(&mut choice)
+                    .expect("Failed to read line");
+                if ["n", "N"].contains(&choice.trim())
+                { 
+                    println!(" - - - ");
+                    return Ok(());
+                };
+                if ["y", "Y"].contains(&choice.trim()) { break; };
+            }
+        },
     }
 
+    let streakhours_activity = crate::db::settings::streak_activity_hours(db)?;
+
     for (index, item) in activitynames.iter().enumerate()
     {
         println!("---- Activity {}", item);
@@ -176,7 +206,7 @@ pub fn printstats(db : &Connection)
         let alltime : f64 = db.query_row(
             &format!("SELECT hourstotal FROM {} WHERE id = ?",
                      crate::db::queries::SQL_TABLEN_ACT),
-                     rusqlite::params![index+1], 
+                     rusqlite::params![activities[index].id], 
                      |row| row.get(0)).unwrap();
 
         println!("-----------------------------------------------------------");
@@ -194,6 +224,10 @@ pub fn printstats(db : &Connection)
         println!(" -> tot/w  {:6.2}", last6wktotallsum / last6wknum as f64);
         println!(" -> avg/d  {:6.2}", 
                  last6wktotallsum / last6wknum as f64 / 7.);
+        println!("Streak of days w/ {} hrs+", streakhours_activity);
+        printstreaks(&retrieve_streaks(db, streakhours_activity,
+                                       Some(activities[index].id),
+                                       now.date_naive())?);
         println!("-----------------------------------------------------------");
     }
 
@@ -202,10 +236,26 @@ pub fn printstats(db : &Connection)
 
 pub fn printstats_year(db : &Connection)
     -> Result<(), Box<dyn error::Error>>
+{
+    printstats_year_of(db, None)
+}
+
+/// prints yearly stats of the given activities only (all if `None`); their
+/// hours are summed up as if they were a single activity
+pub fn printstats_year_of(db : &Connection, activities : Option<&[i32]>)
+    -> Result<(), Box<dyn error::Error>>
 {
     let now = chrono::Local::now();
     let now_year = now.year();
 
+    if let Some(ids) = activities
+    {
+        let names = ids.iter()
+            .map(|id| crate::db::get_activityname_for_id(db, *id))
+            .collect::<Result<Vec<_>, _>>()?;
+        println!("Activities: {}", names.join(", "));
+    }
+
     // collect all yearly stats
 
     let mut statsvec : Vec<helpers::YearCounts> = Vec::new();
@@ -214,7 +264,7 @@ pub fn printstats_year(db : &Connection)
     loop
     {
         if let Ok(stats) = helpers::
-            retrieve_percentages_for_year(db, year, now)
+            retrieve_percentages_for_year_of(db, year, now, activities)
         {
             statsvec.push(stats);
         }
@@ -236,7 +286,9 @@ pub fn printstats_year(db : &Connection)
     // print current year and last year
     // print all time statistics for all past years
 
-    let mut alltime = helpers::YearCounts::new();
+    let mut alltime = helpers::YearCounts::new(
+        &crate::db::settings::day_thresholds(db)?,
+        &crate::db::settings::week_thresholds(db)?);
 
     let mut count = 0;
 
@@ -254,12 +306,169 @@ pub fn printstats_year(db : &Connection)
 
         count += 1;
         s.printpercentages();
+
+        // rollups only make sense over all activities
+        if activities.is_some() { continue; }
+
+        let year = now_year - count + 1;
+        let rows = rollup(db, 1, |id| {
+            Ok(vec![retrieve_total_year(db, year, id)?])
+        })?;
+        printrollup(&rows, "Client / project", &["Hours"]);
     }
 
     if count > 1
     {
         println!("ALL TIME: ");
         alltime.printpercentages();
+
+        if activities.is_none()
+        {
+            let rows = rollup(db, 1, |id| {
+                Ok(vec![retrieve_total_alltime(db, id)?])
+            })?;
+            printrollup(&rows, "Client / project", &["Hours"]);
+        }
+    }
+
+    println!("---------------------------------------------------------------");
+
+    Ok(())
+}
+
+/// prints a report of the dates from..=to: total per activity, its average
+/// per day (over the relevant days, see helpers::relevantddcount_range) and
+/// its share of the range's total
+pub fn printstats_range(db : &Connection, from : NaiveDate, to : NaiveDate)
+    -> Result<(), Box<dyn error::Error>>
+{
+    if to < from
+    {
+        return Err("Range has to end after it begins".into());
+    }
+
+    let now = chrono::Local::now();
+
+    let firstentry = match firstentry_datetime(db)
+    {
+        Ok(firstentry) => firstentry,
+        Err(_) => {
+            println!("No entries in history table");
+            return Ok(());
+        },
+    };
+    let days = relevantddcount_range(&firstentry, from, to, &now);
+
+    let activities = crate::db::get_all_activities(db)?;
+
+    let mut totals : Vec<(String, f64)> = Vec::new();
+
+    for activity in activities
+    {
+        let total = retrieve_total_range(db, from, to, activity.id)?;
+        if total > 0. { totals.push((activity.name, total)); }
+    }
+
+    let sum : f64 = totals.iter().map(|(_, total)| total).sum();
+    let avg = |total : f64| if days > 0 { total / days as f64 } else { 0. };
+
+    println!();
+    println!("---------------------------------------------------------------");
+    println!("From {} to {} ({} relevant days)",
+             from.format("%Y-%m-%d"), to.format("%Y-%m-%d"), days);
+    println!("---------------------------------------------------------------");
+    println!("{:<26}{:>10}{:>10}{:>10}", "Activity", "Total", "avg/d", "Share");
+
+    for (name, total) in &totals
+    {
+        println!("{:<26}{:>10.2}{:>10.2}{:>9.2}%",
+                 name, total, avg(*total), total / sum * 100.);
+    }
+
+    println!("---------------------------------------------------------------");
+    println!("{:<26}{:>10.2}{:>10.2}", "Total", sum, avg(sum));
+    println!("---------------------------------------------------------------");
+
+    // the same per client and project (if any are configured)
+    let rows = rollup(db, 1, |id| {
+        Ok(vec![retrieve_total_range(db, from, to, id)?])
+    })?;
+    printrollup(&rows, "Client / project", &["Total"]);
+
+    Ok(())
+}
+
+/// prints totals per week or month, per activity and overall, of a year or
+/// (`None`) all time; activities w/o any hours in there are left out
+pub fn printstats_series(db : &Connection, period : Period, year : Option<i32>)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let activities = crate::db::get_all_activities(db)?;
+
+    let ids : Vec<i32> = activities.iter().map(|a| a.id).collect();
+    let rows = retrieve_series(db, period, year, &ids)?;
+
+    if rows.is_empty()
+    {
+        println!("No entries in history table for that time");
+        return Ok(());
+    }
+
+    // columns of activities w/ hours only
+    let columns : Vec<usize> = (0..activities.len())
+        .filter(|i| rows.iter().any(|r| r.hours[*i] > 0.))
+        .collect();
+
+    println!();
+    print_activity_header(match period { Period::Week => "Week", _ => "Month" },
+                          &activities, &columns);
+
+    for row in &rows
+    {
+        print!("{:<10}", row.label);
+        for i in &columns { print!("{:>10.2}", row.hours[*i]); }
+        println!("{:>10.2}", row.hours.iter().sum::<f64>());
+    }
+
+    println!("---------------------------------------------------------------");
+
+    Ok(())
+}
+
+/// prints total and average hours per weekday, per activity and overall;
+/// averages are per day since the first entry (days w/o entries included)
+pub fn printstats_weekdays(db : &Connection)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let activities = crate::db::get_all_activities(db)?;
+
+    let ids : Vec<i32> = activities.iter().map(|a| a.id).collect();
+    let rows = retrieve_weekdays(db, &ids, chrono::Local::now().date_naive())?;
+
+    if rows.iter().all(|r| r.days == 0)
+    {
+        println!("No entries in history table");
+        return Ok(());
+    }
+
+    // columns of activities w/ hours only
+    let columns : Vec<usize> = (0..activities.len())
+        .filter(|i| rows.iter().any(|r| r.hours[*i] > 0.))
+        .collect();
+
+    for (title, average) in [("Total", false), ("Avg/day", true)]
+    {
+        println!();
+        print_activity_header(title, &activities, &columns);
+
+        for row in &rows
+        {
+            let per = if average { row.days.max(1) as f64 } else { 1. };
+
+            print!("{:<10}", row.weekday);
+            for i in &columns { print!("{:>10.2}", row.hours[*i] / per); }
+            println!("{:>10.2}", row.hours.iter().sum::<f64>() / per);
+        }
     }
 
     println!("---------------------------------------------------------------");
@@ -267,3 +476,293 @@ pub fn printstats_year(db : &Connection)
     Ok(())
 }
 
+/// prints a histogram of the hours per hour of the day, per activity and
+/// overall, of a year or (`None`) all time; based on tracked sessions only
+/// (see helpers::retrieve_hours_of_day)
+pub fn printstats_hours(db : &Connection, year : Option<i32>)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let activities = crate::db::get_all_activities(db)?;
+
+    let (from, to) = match year
+    {
+        Some(year) => (NaiveDate::from_ymd_opt(year, 1, 1),
+                       NaiveDate::from_ymd_opt(year, 12, 31)),
+        None => (firstentry_datetime(db).ok().map(|f| f.date_naive()),
+                 Some(chrono::Local::now().date_naive())),
+    };
+    let (from, to) = match (from, to)
+    {
+        (Some(from), Some(to)) => (from, to),
+        _ => {
+            println!("No entries in history table for that time");
+            return Ok(());
+        },
+    };
+
+    let ids : Vec<i32> = activities.iter().map(|a| a.id).collect();
+    let buckets = retrieve_hours_of_day(db, &ids, from, to)?;
+
+    // columns of activities w/ hours only
+    let columns : Vec<usize> = (0..activities.len())
+        .filter(|i| buckets[*i].iter().any(|h| *h > 0.))
+        .collect();
+
+    if columns.is_empty()
+    {
+        println!("No tracked sessions for that time (manually entered \
+                 hours have no time of day)");
+        return Ok(());
+    }
+
+    let totals : Vec<f64> = (0..24)
+        .map(|hour| buckets.iter().map(|b| b[hour]).sum())
+        .collect();
+    let max = totals.iter().cloned().fold(0., f64::max);
+
+    println!();
+    print_activity_header("Hour", &activities, &columns);
+
+    for (hour, total) in totals.iter().enumerate()
+    {
+        print!("{:02}-{:02}     ", hour, hour + 1);
+        for i in &columns { print!("{:>10.2}", buckets[*i][hour]); }
+        // bar of up to 20 chars, relative to the busiest hour
+        let bar = "#".repeat((total / max * 20.).round() as usize);
+        println!("{}", format!("{:>10.2}  {}", total, bar).trim_end());
+    }
+
+    println!("---------------------------------------------------------------");
+
+    Ok(())
+}
+
+/// prints work and break time of tracked sessions from..=to per activity,
+/// week and day: ratio of breaks to work, number and average length of breaks
+pub fn printstats_breaks(db : &Connection, from : NaiveDate, to : NaiveDate)
+    -> Result<(), Box<dyn error::Error>>
+{
+    if to < from
+    {
+        return Err("Range has to end after it begins".into());
+    }
+
+    let activities = retrieve_breaks(db, from, to, BreakGroup::Activity)?;
+
+    if activities.is_empty()
+    {
+        println!("No tracked sessions for that time");
+        return Ok(());
+    }
+
+    let overall = BreakRow {
+        label  : "Overall".to_string(),
+        work   : activities.iter().map(|r| r.work).sum(),
+        breaks : activities.iter().map(|r| r.breaks).sum(),
+        count  : activities.iter().map(|r| r.count).sum(),
+    };
+
+    println!();
+    println!("---------------------------------------------------------------");
+    println!("Breaks from {} to {} (tracked time only)",
+             from.format("%Y-%m-%d"), to.format("%Y-%m-%d"));
+    println!("---------------------------------------------------------------");
+    printbreaks(&activities, "Activity");
+    printbreaks(&[overall], "");
+    printbreaks(&retrieve_breaks(db, from, to, BreakGroup::Week)?, "Week");
+    printbreaks(&retrieve_breaks(db, from, to, BreakGroup::Day)?, "Day");
+
+    Ok(())
+}
+
+/// prints a calendar heatmap of the daily totals of an ISO week year: a
+/// column per week, a row per weekday; intensity follows the day thresholds
+/// of the yearly stats (see db::settings); w/ colors unless stdout isn't a
+/// terminal or NO_COLOR is set
+pub fn printheatmap(db : &Connection, year : i32)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let thresholds = crate::db::settings::day_thresholds(db)?;
+    let weeks = NaiveDate::from_ymd_opt(year, 12, 28)
+        .ok_or("Invalid year")?
+        .iso_week().week();
+    let monday = NaiveDate::from_isoywd_opt(year, 1, Weekday::Mon)
+        .ok_or("Invalid year")?;
+    let sunday = monday + chrono::Duration::weeks(weeks as i64) -
+        chrono::Duration::days(1);
+    let today = chrono::Local::now().date_naive();
+
+    let totals = retrieve_day_totals(db, monday, sunday, None)?;
+    let color = io::stdout().is_terminal() &&
+        std::env::var_os("NO_COLOR").is_none();
+
+    // a cell per level: no hours, then below/above every threshold
+    let cells : Vec<String> = (0..=thresholds.len() + 1)
+        .map(|level| heat_cell(level, thresholds.len() + 1, color))
+        .collect();
+
+    println!();
+    println!("Heatmap {} (ISO weeks)", year);
+
+    // week numbers above every 5th column
+    let mut header = vec![' '; weeks as usize + 4];
+    for week in (1..=weeks).filter(|w| *w == 1 || *w % 5 == 0)
+    {
+        for (i, c) in week.to_string().chars().enumerate()
+        {
+            header[week as usize - 1 + i] = c;
+        }
+    }
+    println!("    {}", header.iter().collect::<String>().trim_end());
+
+    for (row, weekday) in ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+        .iter()
+        .enumerate()
+    {
+        print!("{} ", weekday);
+
+        for week in 0..weeks as i64
+        {
+            let date = monday + chrono::Duration::weeks(week) +
+                chrono::Duration::days(row as i64);
+
+            if date > today { break; }
+
+            let hours = totals.iter()
+                .find(|(d, _)| *d == date)
+                .map(|(_, hours)| *hours)
+                .unwrap_or(0.);
+
+            match heat_level(hours, &thresholds)
+            {
+                Some(level) => print!("{}", cells[level + 1]),
+                None        => print!("{}", cells[0]),
+            }
+        }
+        println!();
+    }
+
+    // legend
+    print!("    {} 0", cells[0]);
+    print!("  {} <{}", cells[1], thresholds[0]);
+    for (level, threshold) in thresholds.iter().enumerate()
+    {
+        print!("  {} {}+", cells[level + 2], threshold);
+    }
+    println!(" hrs");
+    println!("---------------------------------------------------------------");
+
+    Ok(())
+}
+
+/// a heatmap cell of `level` (0: no hours) out of `max`
+fn heat_cell(level : usize, max : usize, color : bool) -> String
+{
+    const SHADES : &[char] = &['.', ':', '-', '=', '+', '*', '#', '%', '@'];
+    // 256 color palette, dark to bright green
+    const GREENS : &[u8] = &[22, 28, 34, 40, 46, 82, 118, 154];
+
+    if !color
+    {
+        return SHADES[level * (SHADES.len() - 1) / max].to_string();
+    }
+
+    if level == 0 { return "\x1b[90m·\x1b[0m".to_string(); }
+
+    let green = GREENS[(level - 1) * (GREENS.len() - 1) / (max - 1).max(1)];
+    format!("\x1b[38;5;{}m■\x1b[0m", green)
+}
+
+/// prints totals per tag (of the sessions tagged w/ it)
+pub fn printtags(db : &Connection)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let rows = tag_totals(db, chrono::Local::now())?;
+
+    printrollup(&rows, "Tag", &["Today", "Week", "Month", "All time"]);
+
+    Ok(())
+}
+
+/// prints current and longest streak and the longest gap between streaks
+fn printstreaks(streaks : &Streaks)
+{
+    println!(" -> current     {:4} days", streaks.current);
+    print!(" -> longest     {:4} days", streaks.longest);
+    match streaks.longest_end
+    {
+        Some(end) => println!(" (until {})", end.format("%Y-%m-%d")),
+        None      => println!(),
+    }
+    println!(" -> longest gap {:4} days", streaks.longest_gap);
+}
+
+/// prints the progress towards goals, if there are any
+fn printgoals(progress : &[crate::db::goals::Progress])
+{
+    if progress.is_empty() { return; }
+
+    println!("Goals");
+    for goal in progress
+    {
+        println!(" -> {}", goal.describe());
+    }
+
+    println!("---------------------------------------------------------------");
+}
+
+/// prints the header of a table w/ a column per activity (those at
+/// `columns` of `activities`) and a total column
+fn print_activity_header(title : &str,
+                         activities : &[crate::db::ActivitiesRow],
+                         columns : &[usize])
+{
+    print!("{:<10}", title);
+    for i in columns
+    {
+        // names cut to fit the column
+        let name : String = activities[*i].name.chars().take(9).collect();
+        print!("{:>10}", name);
+    }
+    println!("{:>10}", "Total");
+}
+
+/// prints rows of break statistics (see helpers::retrieve_breaks())
+fn printbreaks(rows : &[BreakRow], title : &str)
+{
+    if !title.is_empty()
+    {
+        println!("{:<21}{:>8}{:>8}{:>8}{:>8}{:>10}",
+                 title, "Work", "Breaks", "Ratio", "Count", "Avg min");
+    }
+
+    for row in rows
+    {
+        // names cut to fit the column
+        let label : String = row.label.chars().take(20).collect();
+        println!("{:<21}{:>8.2}{:>8.2}{:>7.1}%{:>8}{:>10.1}",
+                 label, row.work, row.breaks, row.ratio() * 100., row.count,
+                 row.avg_minutes());
+    }
+
+    println!("---------------------------------------------------------------");
+}
+
+/// prints the rows of a rollup (see helpers::rollup), if there are any
+fn printrollup(rows : &[RollupRow], title : &str, columns : &[&str])
+{
+    if rows.is_empty() { return; }
+
+    print!("{:<26}", title);
+    for column in columns { print!("{:>10}", column); }
+    println!();
+
+    for row in rows
+    {
+        print!("{:<26}", format!("{}{}", "  ".repeat(row.depth), row.name));
+        for hours in &row.hours { print!("{:>10.2}", hours); }
+        println!();
+    }
+
+    println!("---------------------------------------------------------------");
+}
@@ -0,0 +1,225 @@
+//! free-form tags (`meeting`, `review`, ...) on sessions; they slice tracked
+//! time across activities; any stats report can be restricted to a tag by
+//! running it on a filtered copy of the db (see `filtered()`)
+
+use std::error;
+use rusqlite::{Connection, params};
+use super::queries::*;
+use super::sessions;
+
+/// split user input into tags; separated by commas and/or whitespace,
+/// duplicates dropped
+pub fn parse(input : &str) -> Vec<String>
+{
+    let mut tags : Vec<String> = Vec::new();
+
+    for tag in input.split(|c : char| c == ',' || c.is_whitespace())
+    {
+        let tag = tag.trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag)
+        {
+            tags.push(tag.to_string());
+        }
+    }
+
+    tags
+}
+
+/// retrieve all tags in use, ordered by name
+pub fn get(db : &Connection) -> Result<Vec<String>, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(
+        &format!("SELECT name FROM {} WHERE id IN
+                     (SELECT tag FROM {})
+                 ORDER BY name ASC", SQL_TABLEN_TAG, SQL_TABLEN_STG))?;
+
+    let tags = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(tags)
+}
+
+/// retrieve the tags of a session
+pub fn of_session(db : &Connection, sid : i64)
+    -> Result<Vec<String>, Box<dyn error::Error>>
+{
+    let mut stmt = db.prepare(
+        &format!("SELECT t.name FROM {} AS t JOIN {} AS st ON st.tag = t.id
+                 WHERE st.sid = ?1 ORDER BY t.name ASC",
+                 SQL_TABLEN_TAG, SQL_TABLEN_STG))?;
+
+    let tags = stmt
+        .query_map(params![sid], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    Ok(tags)
+}
+
+/// attach tags to a session (tags are created as needed)
+pub fn add(db : &Connection, sid : i64, tags : &[String])
+    -> Result<(), Box<dyn error::Error>>
+{
+    for tag in tags
+    {
+        db.execute(
+            &format!("INSERT OR IGNORE INTO {} (name) VALUES (?1)",
+                     SQL_TABLEN_TAG),
+            params![tag])?;
+
+        db.execute(
+            &format!("INSERT OR IGNORE INTO {} (sid, tag)
+                     SELECT ?1, id FROM {} WHERE name = ?2",
+                     SQL_TABLEN_STG, SQL_TABLEN_TAG),
+            params![sid, tag])?;
+    }
+
+    Ok(())
+}
+
+/// detach a tag from a session
+pub fn remove(db : &Connection, sid : i64, tag : &str)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let changed = db.execute(
+        &format!("DELETE FROM {} WHERE sid = ?1 AND tag =
+                     (SELECT id FROM {} WHERE name = ?2)",
+                 SQL_TABLEN_STG, SQL_TABLEN_TAG),
+        params![sid, tag])?;
+
+    if changed == 0
+    {
+        return Err(format!("Session isn't tagged {}", tag).into());
+    }
+
+    Ok(())
+}
+
+/// in memory copy of the db w/ only the sessions tagged `tag` (and the
+/// history derived from them); activities, projects, clients and settings
+/// are copied as they are, so every stats report works on it unchanged
+pub fn filtered(db : &Connection, tag : &str)
+    -> Result<Connection, Box<dyn error::Error>>
+{
+    let copy = Connection::open_in_memory()?;
+    super::create_tables(&copy)?;
+
+    copy_rows(db, &copy, SQL_TABLEN_CLI, &["id", "name", "added"])?;
+    copy_rows(db, &copy, SQL_TABLEN_PRJ, &["id", "name", "client", "added"])?;
+    copy_rows(db, &copy, SQL_TABLEN_ACT,
+              &["id", "name", "added", "active", "archived", "project"])?;
+    copy_rows(db, &copy, SQL_TABLEN_SET, &["key", "value"])?;
+
+    let mut stmt = db.prepare(
+        &format!("SELECT sid FROM {} WHERE tag =
+                     (SELECT id FROM {} WHERE name = ?1)",
+                 SQL_TABLEN_STG, SQL_TABLEN_TAG))?;
+    let sids : Vec<i64> = stmt
+        .query_map(params![tag], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+
+    let tx = copy.unchecked_transaction()?;
+
+    for sid in sids
+    {
+        let session = sessions::get_by_sid(db, sid)?;
+        sessions::enter(&tx, &session.started, &session.ended, session.id,
+                        session.kind, session.source)?;
+    }
+
+    tx.commit()?;
+
+    Ok(copy)
+}
+
+fn copy_rows(from : &Connection, to : &Connection, table : &str,
+             columns : &[&str])
+    -> Result<(), Box<dyn error::Error>>
+{
+    let placeholders = (1..=columns.len())
+        .map(|i| format!("?{}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let columns = columns.join(", ");
+
+    let insert = format!("INSERT INTO {} ({}) VALUES ({})",
+                         table, columns, placeholders);
+
+    let mut stmt = from.prepare(
+        &format!("SELECT {} FROM {}", columns, table))?;
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()?
+    {
+        let values : Vec<rusqlite::types::Value> =
+            (0..row.as_ref().column_count())
+            .map(|i| row.get(i))
+            .collect::<Result<_, _>>()?;
+
+        to.execute(&insert, rusqlite::params_from_iter(values))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test;
+    use chrono::{Duration, Local, NaiveDate, TimeZone};
+    use sessions::{Kind, Source};
+
+    #[test]
+    fn parse_works()
+    {
+        assert_eq!(parse("meeting, review deep-work,,meeting"),
+                   vec!["meeting", "review", "deep-work"]);
+        assert!(parse("  ").is_empty());
+    }
+
+    #[test]
+    fn tag_and_filter()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        let beg = Local.with_ymd_and_hms(2024, 01, 10, 9, 0, 0).unwrap();
+        let one = sessions::enter(&db, &beg, &(beg + Duration::hours(2)), 1,
+                                  Kind::Work, Source::Timer).unwrap();
+        let two = sessions::enter(&db, &(beg + Duration::hours(2)),
+                                  &(beg + Duration::hours(3)), 2,
+                                  Kind::Work, Source::Timer).unwrap();
+        sessions::enter(&db, &(beg + Duration::hours(3)),
+                        &(beg + Duration::hours(4)), 2,
+                        Kind::Work, Source::Manual).unwrap();
+
+        add(&db, one, &parse("meeting,review")).unwrap();
+        add(&db, two, &parse("meeting")).unwrap();
+        assert_eq!(of_session(&db, one).unwrap(), vec!["meeting", "review"]);
+        assert_eq!(get(&db).unwrap(), vec!["meeting", "review"]);
+
+        remove(&db, one, "review").unwrap();
+        assert!(remove(&db, one, "review").is_err());
+        assert_eq!(get(&db).unwrap(), vec!["meeting"]);
+
+        // filtered copy only holds the tagged sessions' time
+        let copy = filtered(&db, "meeting").unwrap();
+        let total : f64 = copy.query_row(
+            &format!("SELECT SUM(hourstotal) FROM {}", SQL_TABLEN_ACT),
+            [], |row| row.get(0)).unwrap();
+        assert!((total - 3.).abs() <= epsilon);
+        assert_eq!(crate::db::get_activities(&copy, true).unwrap().len(), 4);
+
+        // tags go w/ their session
+        sessions::remove(&db, two).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 01, 10).unwrap();
+        assert_eq!(sessions::get(&db, day, day).unwrap().len(), 2);
+        let tagged : i32 = db.query_row(
+            &format!("SELECT COUNT(*) FROM {}", SQL_TABLEN_STG),
+            [], |row| row.get(0)).unwrap();
+        assert_eq!(tagged, 1);
+    }
+}
@@ -0,0 +1,256 @@
+//! persisted running timer; allows starting a timer in one invocation of the
+//! application and stopping it in another (see `timetracker start/stop`),
+//! and lets the interactive tracker survive a closed terminal or crash
+
+use std::error;
+use chrono::{DateTime, Local};
+use rusqlite::{Connection, OptionalExtension, params};
+use super::queries::*;
+use super::sessions::{Kind, Source};
+
+/// representing the (single) row of the timer table;
+/// `started` is the beginning of the current work or break phase;
+/// `tags` are attached to every session the timer records, `note` to every
+/// work session
+#[derive(Debug, Clone)]
+pub struct TimerRow {
+    pub id: i32,
+    pub started: DateTime<Local>,
+    pub onbreak: bool,
+    pub interactive: bool,
+    pub tags: Vec<String>,
+    pub note: Option<String>,
+}
+
+impl TimerRow
+{
+    /// kind of session the current phase makes up
+    pub fn kind(&self) -> Kind
+    {
+        if self.onbreak { Kind::Break } else { Kind::Work }
+    }
+}
+
+/// retrieve running timer, if there is one
+pub fn get(db : &Connection) -> Result<Option<TimerRow>, Box<dyn error::Error>>
+{
+    type Row = (i32, String, bool, bool, String, Option<String>);
+
+    let row : Option<Row> = db.query_row(
+        &format!("SELECT id, started, onbreak, interactive, tags, note
+                 FROM {} LIMIT 1", SQL_TABLEN_TMR),
+        params![],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?,
+                  row.get(4)?, row.get(5)?))
+        ).optional()?;
+
+    match row
+    {
+        Some((id, started, onbreak, interactive, tags, note)) => {
+            let started = DateTime::parse_from_rfc3339(&started)?
+                .with_timezone(&Local);
+            let tags = super::tags::parse(&tags);
+            Ok(Some(TimerRow {
+                id, started, onbreak, interactive, tags, note
+            }))
+        },
+        None => Ok(None),
+    }
+}
+
+/// start a timer (in work state) for given activity; only one timer can run
+/// at a time; `interactive` denotes timers of the tracker loop (lib.rs) which
+/// are expected to end with the process, anything left over is orphaned
+pub fn start(
+    db          : &Connection,
+    actid       : i32,
+    started     : &DateTime<Local>,
+    interactive : bool,
+    tags        : &[String],
+    ) -> Result<(), Box<dyn error::Error>>
+{
+    if get(db)?.is_some()
+    {
+        return Err("A timer is already running".into());
+    }
+
+    super::get_activityname_for_id(db, actid)?;
+
+    db.execute(
+        &format!("INSERT INTO {} (id, started, onbreak, interactive, tags)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                 SQL_TABLEN_TMR),
+        params![actid, started.to_rfc3339(), false, interactive,
+                tags.join(",")])?;
+
+    Ok(())
+}
+
+/// set (or w/ `None`/empty text remove) the note of the running timer
+pub fn set_note(db : &Connection, note : Option<&str>)
+    -> Result<(), Box<dyn error::Error>>
+{
+    running(db)?;
+
+    let note = note.map(|n| n.trim()).filter(|n| !n.is_empty());
+
+    db.execute(&format!("UPDATE {} SET note = ?1", SQL_TABLEN_TMR),
+               params![note])?;
+
+    Ok(())
+}
+
+/// end the current phase at `at` and begin the other one (work <-> break);
+/// the finished phase is entered into the db within the same transaction;
+/// returns the timer as it was before switching
+pub fn switch(db : &Connection, at : &DateTime<Local>)
+    -> Result<TimerRow, Box<dyn error::Error>>
+{
+    let timer = running(db)?;
+    let tx = db.unchecked_transaction()?;
+
+    let sid = super::sessions::enter(&tx, &timer.started, at, timer.id,
+                                     timer.kind(), Source::Timer)?;
+    super::tags::add(&tx, sid, &timer.tags)?;
+    if timer.kind() == Kind::Work
+    {
+        super::sessions::set_note(&tx, sid, timer.note.as_deref())?;
+    }
+
+    tx.execute(
+        &format!("UPDATE {} SET started=?1, onbreak=?2", SQL_TABLEN_TMR),
+        params![at.to_rfc3339(), !timer.onbreak])?;
+
+    tx.commit()?;
+
+    Ok(timer)
+}
+
+/// stop the timer at `at`; the running phase is entered into the db within
+/// the same transaction; returns the stopped timer
+pub fn finish(db : &Connection, at : &DateTime<Local>)
+    -> Result<TimerRow, Box<dyn error::Error>>
+{
+    let timer = running(db)?;
+
+    if *at < timer.started
+    {
+        return Err("End time lies before start of the timer".into());
+    }
+
+    let tx = db.unchecked_transaction()?;
+
+    let sid = super::sessions::enter(&tx, &timer.started, at, timer.id,
+                                     timer.kind(), Source::Timer)?;
+    super::tags::add(&tx, sid, &timer.tags)?;
+    if timer.kind() == Kind::Work
+    {
+        super::sessions::set_note(&tx, sid, timer.note.as_deref())?;
+    }
+
+    tx.execute(&format!("DELETE FROM {}", SQL_TABLEN_TMR), params![])?;
+    tx.commit()?;
+
+    Ok(timer)
+}
+
+/// remove the timer without entering anything into the db
+pub fn discard(db : &Connection) -> Result<TimerRow, Box<dyn error::Error>>
+{
+    let timer = running(db)?;
+
+    db.execute(&format!("DELETE FROM {}", SQL_TABLEN_TMR), params![])?;
+
+    Ok(timer)
+}
+
+fn running(db : &Connection) -> Result<TimerRow, Box<dyn error::Error>>
+{
+    match get(db)?
+    {
+        Some(timer) => Ok(timer),
+        None => Err("No timer is running".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::test;
+    use chrono::TimeZone;
+
+    fn hoursonday(db : &Connection, id : i32, date : &str) -> f64
+    {
+        db.query_row(
+            &format!("SELECT hoursonday FROM {} WHERE id=?1 AND date=?2",
+                     SQL_TABLEN_HIS),
+            params![id, date],
+            |row| row.get(0)).unwrap_or(0.)
+    }
+
+    #[test]
+    fn start_stop_roundtrip()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+
+        assert!(get(&db).unwrap().is_none());
+        assert!(discard(&db).is_err());
+
+        let dt = Local.with_ymd_and_hms(2024, 01, 10, 8, 30, 0).unwrap();
+        start(&db, 2, &dt, false, &[]).unwrap();
+
+        // only one timer at a time, and only for existing activities
+        assert!(start(&db, 1, &dt, false, &[]).is_err());
+
+        let timer = get(&db).unwrap().unwrap();
+        assert_eq!(timer.id, 2);
+        assert_eq!(timer.started, dt);
+        assert!(!timer.onbreak);
+        assert!(!timer.interactive);
+
+        let timer = discard(&db).unwrap();
+        assert_eq!(timer.id, 2);
+        assert!(get(&db).unwrap().is_none());
+        assert!((hoursonday(&db, 2, "2024-01-10") - 0.).abs() <= 0.001);
+
+        assert!(start(&db, 42, &dt, false, &[]).is_err());
+    }
+
+    #[test]
+    fn work_break_phases()
+    {
+        let mut db = Connection::open_in_memory().unwrap();
+        test::initialize_db(&mut db);
+        test::populate_db_w_activities(&mut db);
+        let epsilon = 0.001;
+
+        let dt = Local.with_ymd_and_hms(2024, 01, 10, 8, 00, 0).unwrap();
+        start(&db, 1, &dt, true, &["deep-work".to_string()]).unwrap();
+        set_note(&db, Some("refactoring")).unwrap();
+
+        // 2 hours of work, 1 hour break, 1.5 hours of work
+        switch(&db, &(dt + chrono::Duration::hours(2))).unwrap();
+        assert!(get(&db).unwrap().unwrap().onbreak);
+        switch(&db, &(dt + chrono::Duration::hours(3))).unwrap();
+        assert!(!get(&db).unwrap().unwrap().onbreak);
+
+        // trimmed end can't lie before start of current phase
+        assert!(finish(&db, &dt).is_err());
+        finish(&db, &(dt + chrono::Duration::minutes(270))).unwrap();
+
+        assert!(get(&db).unwrap().is_none());
+        assert!((hoursonday(&db, 1, "2024-01-10") - 3.5).abs() <= epsilon);
+
+        // every phase carries the timer's tags, work phases its note
+        let day = dt.date_naive();
+        for session in super::super::sessions::get(&db, day, day).unwrap()
+        {
+            assert_eq!(super::super::tags::of_session(&db, session.sid)
+                       .unwrap(), vec!["deep-work"]);
+            assert_eq!(session.note.is_some(), session.kind == Kind::Work);
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::error;
 use std::io::{self, Write};
 use rusqlite::Connection;
 
+pub mod cli;
 pub mod db;
 pub mod tracker;
 #[cfg(test)]
@@ -17,7 +18,22 @@ pub fn print_acts_get_choice(
     )
     ->Result<i32, Box<dyn error::Error>>
 {
-    let activities = db::get_activities(db, activated)?;
+    print_list_get_choice(db::get_activities(db, activated)?)
+}
+
+/// like print_acts_get_choice(), but offering active and deactivated
+/// activities alike
+pub fn print_all_acts_get_choice(db : &mut Connection)
+    ->Result<i32, Box<dyn error::Error>>
+{
+    let activities = db::get_all_activities(db)?;
+
+    print_list_get_choice(activities)
+}
+
+fn print_list_get_choice(activities : Vec<db::ActivitiesRow>)
+    ->Result<i32, Box<dyn error::Error>>
+{
     let mut activities_ids : Vec<i32> = Vec::new();
     let mut idstr = String::new();
     let mut idint;
@@ -28,7 +44,14 @@ pub fn print_acts_get_choice(
 
     for activity in activities
     {
-        println!("{}\t{}", activity.id, activity.name);
+        if activity.active
+        {
+            println!("{}\t{}", activity.id, activity.name);
+        }
+        else
+        {
+            println!("{}\t{} (deactivated)", activity.id, activity.name);
+        }
         activities_ids.push(activity.id);
     }
 
@@ -58,7 +81,20 @@ pub fn print_acts_get_choice(
     Ok(idint)
 }
 
-/// running loop when tracker is tracking an activity
+/// prompt for (optional) tags of the time about to be tracked/entered
+fn read_tags() -> Vec<String>
+{
+    db::tags::parse(&read_input("Tags (comma separated, empty for none): "))
+}
+
+/// prompt for an (optional) note on what is/was done
+fn read_note() -> Option<String>
+{
+    Some(read_input("Note (empty for none): ")).filter(|note| !note.is_empty())
+}
+
+/// running loop when tracker is tracking an activity;
+/// the running phase is persisted (db::timer) to survive a crash
 pub fn track(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
 {
     let idint;
@@ -69,6 +105,21 @@ pub fn track(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
         Err(err)  => { eprintln!("{}", err); return Ok(()); }
     }
 
+    let tags = read_tags();
+    let note = read_note();
+
+    let tx = db.transaction()?;
+
+    if let Err(err) =
+        db::timer::start(&tx, idint, &chrono::Local::now(), true, &tags)
+    {
+        eprintln!("{}", err);
+        return Ok(());
+    }
+
+    db::timer::set_note(&tx, note.as_deref())?;
+    tx.commit()?;
+
     let mut totalwork = chrono::Duration::zero();
     let mut totalpaus = chrono::Duration::zero();
 
@@ -79,13 +130,31 @@ pub fn track(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
     {
         println!("Started work timer");
 
-        let mut datetime_beg = chrono::Local::now();
+        // goals of the activity and overall ones are shown once, the first
+        // (activity goals before overall ones, shortest period first) w/ the
+        // timer as well
+        let mut goals : Vec<_> =
+            db::goals::progress(db, chrono::Local::now().date_naive())?
+            .into_iter()
+            .filter(|p| p.goal.activity.is_none() ||
+                        p.goal.activity == Some(idint))
+            .collect();
+        goals.sort_by_key(|p| (p.goal.activity.is_none(), p.goal.period));
+
+        for goal in &goals { println!("  {}", goal.describe()); }
+
         // endloop is a bool indicating whether loop should be stopped
-        let mut endloop = crate::tracker::workloop().unwrap();
+        let mut endloop =
+            crate::tracker::workloop(goals.into_iter().next()).unwrap();
         let mut datetime_end = chrono::Local::now();
-        let mut duration = datetime_end.signed_duration_since(datetime_beg);
 
-        db::enter_into_db(db, &datetime_beg, &datetime_end, idint)?;
+        // enters the work phase into db (and starts break phase)
+        let mut timer = match end_phase(db, &datetime_end, endloop)?
+        {
+            Some(timer) => timer,
+            None        => return Ok(()),
+        };
+        let mut duration = datetime_end.signed_duration_since(timer.started);
 
         totalwork = totalwork + duration;
 
@@ -99,10 +168,15 @@ pub fn track(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
         println!("Started break timer");
 
         // endloop is a bool indicating whether loop should be stopped
-        datetime_beg = chrono::Local::now();
-        endloop = crate::tracker::workloop().unwrap();
+        endloop = crate::tracker::workloop(None).unwrap();
         datetime_end = chrono::Local::now();
-        duration = datetime_end.signed_duration_since(datetime_beg);
+
+        timer = match end_phase(db, &datetime_end, endloop)?
+        {
+            Some(timer) => timer,
+            None        => return Ok(()),
+        };
+        duration = datetime_end.signed_duration_since(timer.started);
 
         totalpaus = totalpaus + duration;
 
@@ -123,22 +197,187 @@ pub fn track(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
              totalpaus.num_hours(),
              totalpaus.num_minutes() % 60,
              totalpaus.num_seconds() % 60);
-    println!("Pause percentage: {:.2}%", 
-             totalpaus.num_seconds() as f64 / totalwork.num_seconds() as f64);
+    println!("Pause percentage: {:.2}%",
+             totalpaus.num_seconds() as f64 / totalwork.num_seconds() as f64
+             * 100.);
 
     Ok(())
 }          
 
+/// end the running phase of the timer at `at`, either finishing it or
+/// switching to the next phase; `None` (and the user told) if the timer has
+/// been stopped elsewhere in the meantime (e.g. `timetracker stop`)
+fn end_phase(db : &Connection, at : &chrono::DateTime<chrono::Local>,
+             finish : bool)
+    -> Result<Option<db::timer::TimerRow>, Box<dyn error::Error>>
+{
+    if db::timer::get(db)?.is_none()
+    {
+        println!();
+        println!("The timer has been stopped elsewhere (its time is entered \
+                  already)");
+        return Ok(None);
+    }
+
+    let timer = if finish {
+        db::timer::finish(db, at)?
+    } else {
+        db::timer::switch(db, at)?
+    };
+
+    Ok(Some(timer))
+}
+
+/// on startup: deal with a timer left over from a tracking session that
+/// didn't end properly (closed terminal, crash, ...)
+pub fn recover(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
+{
+    let timer = match db::timer::get(db)?
+    {
+        Some(timer) => timer,
+        None        => return Ok(()),
+    };
+
+    let name  = db::get_activityname_for_id(db, timer.id)?;
+    let now   = chrono::Local::now();
+    let phase = if timer.onbreak { "break" } else { "work" };
+    let since = now.signed_duration_since(timer.started);
+
+    // timers started via `timetracker start` are meant to keep running
+    if !timer.interactive
+    {
+        println!("Timer running for {} since {} (stop w/ `timetracker stop`)",
+                 name, timer.started.format("%Y-%m-%d %H:%M"));
+        return Ok(());
+    }
+
+    println!();
+    println!("---------------------------------------------------------------");
+    println!("Found a tracking session which has not been ended properly");
+    println!("  Activity: {}", name);
+    println!("  Phase   : {} since {} ({:02}:{:02} hours)",
+             phase, timer.started.format("%Y-%m-%d %H:%M"),
+             since.num_hours(), since.num_minutes() % 60);
+    if !timer.onbreak && since >= chrono::Duration::hours(24)
+    {
+        println!("  Times of >= 24 hours aren't supported, consider trimming");
+    }
+    println!("---------------------------------------------------------------");
+    println!("Options: ");
+    println!();
+    println!("  (c)ommit work time up until now");
+    println!("  (t)rim, commit work time up until a time you enter");
+    println!("  (d)iscard session");
+    println!("  (k)eep (another instance might still be tracking)");
+    println!();
+    print!("Your option: ");
+    io::stdout().flush().unwrap();
+
+    let mut opt = String::new();
+
+    loop
+    {
+        opt.clear();
+        io::stdin().read_line(&mut opt).expect("Failed to read line");
+        if ["c", "t", "d", "k"].contains(&opt.trim()) { break; }
+    }
+
+    match opt.trim()
+    {
+        "c" => {
+            // fails for work phases of >= 24 hours, timer is kept then
+            match db::timer::finish(db, &now)
+            {
+                Ok(_)    => println!("Session committed"),
+                Err(err) => eprintln!("{} (session kept)", err),
+            }
+        },
+        "t" => {
+            print!("End time (HH:MM or YYYY-MM-DD HH:MM): ");
+            io::stdout().flush().unwrap();
+
+            let mut input = String::new();
+
+            loop
+            {
+                input.clear();
+                io::stdin().read_line(&mut input).expect("Failed to read line");
+
+                let end = cli::parse_datetime(
+                    input.trim(), timer.started.date_naive());
+
+                match end
+                {
+                    Some(end) if end >= timer.started && end <= now => {
+                        match db::timer::finish(db, &end)
+                        {
+                            Ok(_)    => break,
+                            Err(err) => print!("{}, try again: ", err),
+                        }
+                    },
+                    _ => print!("Invalid time, try again: "),
+                }
+                io::stdout().flush().unwrap();
+            }
+
+            println!("Session trimmed and committed");
+        },
+        "d" => {
+            db::timer::discard(db)?;
+            println!("Session discarded");
+        },
+        _ => (),
+    }
+
+    Ok(())
+}
+
 /// statistics on data of sql db; only reads from db;
 pub fn statsnormal(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
 {
     db::stat::printstats(db)?;
+
+    if read_yes_no("Show breaks of the last 4 weeks? (y/n): ")
+    {
+        let today = Local::now().date_naive();
+        db::stat::printstats_breaks(db, today - Duration::days(27), today)?;
+    }
     Ok(())
 }
 
 pub fn statsyear(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
 {
-    db::stat::printstats_year(db)?;
+    if !read_yes_no("Only for a selection of activities? (y/n): ")
+    {
+        db::stat::printstats_year(db)?;
+
+        if read_yes_no("Show calendar heatmap of this year? (y/n): ")
+        {
+            db::stat::printheatmap(db, Local::now().iso_week().year())?;
+        }
+        if read_yes_no("Show hours per weekday? (y/n): ")
+        {
+            db::stat::printstats_weekdays(db)?;
+        }
+        if read_yes_no("Show hours per time of day? (y/n): ")
+        {
+            db::stat::printstats_hours(db, None)?;
+        }
+        return Ok(());
+    }
+
+    // pick activities one after another, 'q' once done
+    let mut ids : Vec<i32> = Vec::new();
+
+    while let Ok(id) = print_all_acts_get_choice(db)
+    {
+        if !ids.contains(&id) { ids.push(id); }
+        println!("Selected: {} ('q' once done)", ids.len());
+    }
+
+    if ids.is_empty() { return Ok(()); }
+
+    db::stat::printstats_year_of(db, Some(&ids))?;
     Ok(())
 }
 
@@ -153,6 +392,12 @@ pub fn conf(db : &mut Connection) -> Result<()>
         println!("  (a)dd new");
         println!("  (d)eactivate");
         println!("  (r)eactivate");
+        println!("  re(n)ame");
+        println!("  (m)erge into another activity");
+        println!("  (x) delete permanently");
+        println!("  (p)rojects and clients");
+        println!("  (t)hresholds of yearly stats and streaks");
+        println!("  (g)oals");
         println!("  (q)uit (back to main menu)");
         println!();
         print!("Your option: ");
@@ -171,16 +416,10 @@ pub fn conf(db : &mut Connection) -> Result<()>
             io::stdin().read_line(&mut name).expect("Failed to read line");
             name = name.trim().to_string();
 
-            let date = chrono::Local::now().format("%Y-%m-%d").to_string();
-
-            db.execute(
-                &format!("INSERT INTO {}
-                        (name, added, hourstotal) 
-                        VALUES
-                        (?1, ?2, ?3)",
-                        crate::db::queries::SQL_TABLEN_ACT),
-                        rusqlite::params![name, date, 0.]
-                      )?;
+            if let Err(err) = db::add_activity(db, &name)
+            {
+                eprintln!("{}", err);
+            }
         }
         else if opt == "d"
         {
@@ -192,120 +431,333 @@ pub fn conf(db : &mut Connection) -> Result<()>
                 Err(err)  => { eprintln!("{}", err); return Ok(()); }
             }
 
-            let id_lowestinactive : i32 = db.query_row(
-                &format!(
-                    "SELECT id FROM {} WHERE id < 0 ORDER BY id ASC",
-                    db::queries::SQL_TABLEN_ACT), (),
-                    |row| row.get(0)).unwrap_or_else(|_| 0);
+            match db::deactivate_activity(db, id)
+            {
+                Ok(())   => println!("Activity deactivated"),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        else if opt == "r"
+        {
+            let id;
 
-            // ideally a transaction & rollback should be used here
+            match print_acts_get_choice(db, false)
+            {
+                Ok(value) => { id = value },
+                Err(err)  => { eprintln!("{}", err); return Ok(()); }
+            }
 
-			db.execute("PRAGMA foreign_keys=OFF;", rusqlite::params![])?;
+            match db::reactivate_activity(db, id)
+            {
+                Ok(())   => println!("Activity reactivated"),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        else if opt == "n"
+        {
+            let id = match print_all_acts_get_choice(db)
+            {
+                Ok(value) => value,
+                Err(err)  => { eprintln!("{}", err); return Ok(()); }
+            };
 
-            db.execute(
-                &format!("UPDATE {} SET id = ?1 WHERE id = ?2",
-                         crate::db::queries::SQL_TABLEN_ACT),
-                         rusqlite::params![id_lowestinactive - 1, id])?;
+            print!("Enter new activity name: ");
+            io::stdout().flush().unwrap();
+            let mut name = String::new();
+            io::stdin().read_line(&mut name).expect("Failed to read line");
 
-            db.execute(
-                &format!("UPDATE {} SET id = ?1 WHERE id = ?2",
-                         crate::db::queries::SQL_TABLEN_HIS),
-                         rusqlite::params![id_lowestinactive - 1, id])?;
+            match db::rename_activity(db, id, &name)
+            {
+                Ok(())   => println!("Activity renamed"),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        else if opt == "m"
+        {
+            println!("Which activity should be merged (and removed)?");
+            let from = match print_all_acts_get_choice(db)
+            {
+                Ok(value) => value,
+                Err(err)  => { eprintln!("{}", err); return Ok(()); }
+            };
 
-            // decrement all subsequent IDs in activities and history table
+            println!("Into which activity should it be merged?");
+            let into = match print_all_acts_get_choice(db)
+            {
+                Ok(value) => value,
+                Err(err)  => { eprintln!("{}", err); return Ok(()); }
+            };
 
-            let mut id = id + 1;
+            match db::merge_activities(db, from, into)
+            {
+                Ok(rows) => println!("Activity merged ({} days moved)", rows),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        else if opt == "x"
+        {
+            let id = match print_all_acts_get_choice(db)
+            {
+                Ok(value) => value,
+                Err(err)  => { eprintln!("{}", err); return Ok(()); }
+            };
+
+            // user confirmation
+            let rows = db::count_history_rows(db, id)?;
+            println!("---------------------------------------------------------------");
+            println!("Deleting {} removes {} history entries (days) along w/",
+                     db::get_activityname_for_id(db, id).unwrap_or_default(),
+                     rows);
+            println!("all its tracked sessions; this can't be undone");
+            println!("---------------------------------------------------------------");
+            print!("Should the activity be deleted? (y/n): ");
+            io::stdout().flush().unwrap();
+            let mut choice = String::new();
 
             loop
             {
-                // activities table
-                let changed = db.execute(
-                    &format!("UPDATE {} SET
-                             id = ?1 WHERE id = ?2",
-                             crate::db::queries::SQL_TABLEN_ACT),
-                             rusqlite::params![id-1, id])
-                    .map_err(|_| return ()).unwrap();
-
-                if changed == 0 { break };
-
-                // history table
-                let _ = db.execute(
-                    &format!("UPDATE {} SET
-                             id = ?1 WHERE id = ?2",
-                             crate::db::queries::SQL_TABLEN_HIS),
-                             rusqlite::params![id-1, id])
-                    .map_err(|_| return ());
-
-                id += 1;
+                choice.clear();
+                io::stdin().read_line(&mut choice).expect("Failed to read line");
+                if ["y", "n"].contains(&choice.trim()) { break; }
             }
 
-           db.execute("PRAGMA foreign_keys=ON;", rusqlite::params![])?;
+            if choice.trim() == "n" { continue; }
 
-           println!("Activity deactivated");
+            match db::delete_activity(db, id)
+            {
+                Ok(rows) => println!("Activity deleted ({} days removed)", rows),
+                Err(err) => eprintln!("{}", err),
+            }
         }
-        else if opt == "r"
+        else if opt == "p"
         {
-            let id;
+            conf_projects(db)?;
+        }
+        else if opt == "t"
+        {
+            conf_thresholds(db);
+        }
+        else if opt == "g"
+        {
+            conf_goals(db);
+        }
+        else if opt == "q"
+        {
+            break;
+        }
+    }
 
-            match print_acts_get_choice(db, false)
+    Ok(())
+}
+
+/// configuration of the hour thresholds of the yearly statistics and streaks
+fn conf_thresholds(db : &Connection)
+{
+    use db::settings::*;
+
+    for (what, key) in [("Thresholds of days", KEY_DAY_THRESHOLDS),
+                        ("Thresholds of weeks", KEY_WEEK_THRESHOLDS),
+                        ("Streak, hours a day", KEY_STREAK_HOURS),
+                        ("Streak, hours a day of an activity",
+                         KEY_STREAK_ACTIVITY)]
+    {
+        let single = key == KEY_STREAK_HOURS || key == KEY_STREAK_ACTIVITY;
+        let current = match key
+        {
+            KEY_DAY_THRESHOLDS  => day_thresholds(db),
+            KEY_WEEK_THRESHOLDS => week_thresholds(db),
+            KEY_STREAK_HOURS    => streak_hours(db).map(|h| vec![h]),
+            _                   => streak_activity_hours(db).map(|h| vec![h]),
+        };
+
+        match current
+        {
+            Ok(current) => println!("{}: {}", what,
+                                    current.iter()
+                                    .map(|t| t.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")),
+            Err(err) => eprintln!("{}", err),
+        }
+
+        println!("  new hours ({}), 'default' to reset, empty keeps",
+                 if single { "e.g. 4" } else { "e.g. 2, 4, 8" });
+        let input = read_input("Your input: ");
+
+        let result = match input.as_str()
+        {
+            ""        => Ok(()),
+            "default" => reset(db, key),
+            _ if single => set_threshold(db, key, &input).map(|_| ()),
+            _         => set_thresholds(db, key, &input).map(|_| ()),
+        };
+
+        if let Err(err) = result { eprintln!("{}", err); }
+    }
+}
+
+/// configuration of the hour goals of activities and overall ones
+fn conf_goals(db : &mut Connection)
+{
+    loop
+    {
+        println!();
+        match db::goals::progress(db, Local::now().date_naive())
+        {
+            Ok(progress) if progress.is_empty() => println!("No goals set"),
+            Ok(progress) => for goal in progress
             {
-                Ok(value) => { id = value },
-                Err(err)  => { eprintln!("{}", err); return Ok(()); }
+                println!("{}", goal.describe());
+            },
+            Err(err) => eprintln!("{}", err),
+        }
+        println!();
+        println!("  (s)et a goal");
+        println!("  (r)emove a goal");
+        println!("  (q)uit (back to options)");
+        println!();
+
+        let opt = read_input("Your option: ");
+        if opt == "q" { break; }
+        if opt != "s" && opt != "r" { continue; }
+
+        let activity = if read_yes_no("Goal of all activities? (y/n): ")
+        {
+            None
+        }
+        else
+        {
+            match print_acts_get_choice(db, true)
+            {
+                Ok(value) => Some(value),
+                Err(err)  => { eprintln!("{}", err); continue; }
             }
-            
-            let id_highestactive : i32 = db.query_row(
-                &format!(
-                    "SELECT id FROM {} WHERE id > 0 
-                    ORDER BY id DESC LIMIT 1",
-                    db::queries::SQL_TABLEN_ACT), (),
-                    |row| row.get(0)).unwrap_or_else(|_| 0);
-
-			db.execute("PRAGMA foreign_keys=OFF;", rusqlite::params![])?;
-
-            // update activities
-            
-            db.execute(
-                &format!("UPDATE {} SET id=?1 WHERE id = ?2",
-                        crate::db::queries::SQL_TABLEN_ACT),
-                        rusqlite::params![id_highestactive+1, id])?;
-
-            // update history
-
-            db.execute(
-                &format!("UPDATE {} SET id=?1 WHERE id = ?2",
-                        crate::db::queries::SQL_TABLEN_HIS),
-                        rusqlite::params![id_highestactive+1, id])?;
-
-            // reshuffle the inactive IDs
-            
-            let mut id = id - 1;
+        };
 
-            loop
+        let period = loop
+        {
+            let input = read_input("Per (d)ay, (w)eek or (m)onth: ");
+            if let Some(period) = db::goals::Period::parse(&input)
             {
-                // activities table
-                let changed = db.execute(
-                    &format!("UPDATE {} SET
-                             id = ?1 WHERE id = ?2",
-                             crate::db::queries::SQL_TABLEN_ACT),
-                             rusqlite::params![id+1, id])
-                    .map_err(|_| return ()).unwrap();
-
-                if changed == 0 { break };
-
-                // history table
-                let _ = db.execute(
-                    &format!("UPDATE {} SET
-                             id = ?1 WHERE id = ?2",
-                             crate::db::queries::SQL_TABLEN_HIS),
-                             rusqlite::params![id+1, id])
-                    .map_err(|_| return ());
-
-                id -= 1;
+                break period;
             }
+        };
+
+        let result = if opt == "s"
+        {
+            let hours = read_hours("Hours (HH:MM or decimal): ");
+            db::goals::set(db, activity, period, hours)
+        }
+        else
+        {
+            db::goals::remove(db, activity, period)
+        };
+
+        if let Err(err) = result { eprintln!("{}", err); }
+    }
+}
+
+/// configuration of the grouping of activities: client -> project -> activity
+pub fn conf_projects(db : &mut Connection) -> Result<()>
+{
+    loop
+    {
+        println!();
+        println!("Projects and clients: ");
+        println!();
+        println!("  add (c)lient");
+        println!("  add (p)roject");
+        println!("  (a)ssign activity to project");
+        println!("  (q)uit (back to configuration)");
+        println!();
+        print!("Your option: ");
+        io::stdout().flush().unwrap();
 
-           db.execute("PRAGMA foreign_keys=ON;", rusqlite::params![])?;
+        let mut opt = String::new();
+        io::stdin().read_line(&mut opt).expect("Failed to read line");
+        opt = opt.trim().to_string();
+
+        if opt == "c"
+        {
+            print!("Enter client name: ");
+            io::stdout().flush().unwrap();
+            let mut name = String::new();
+            io::stdin().read_line(&mut name).expect("Failed to read line");
 
-           println!("Activity reactivated");
+            match db::projects::add_client(db, &name)
+            {
+                Ok(_)    => println!("Client added"),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        else if opt == "p"
+        {
+            print!("Enter project name: ");
+            io::stdout().flush().unwrap();
+            let mut name = String::new();
+            io::stdin().read_line(&mut name).expect("Failed to read line");
+
+            let clients = db::projects::get_clients(db).unwrap_or_default();
+            println!("ID\tClient");
+            for client in clients.iter()
+            {
+                println!("{}\t{}", client.id, client.name);
+            }
+            print!("Client ID (empty for none): ");
+            io::stdout().flush().unwrap();
+            let mut client = String::new();
+            io::stdin().read_line(&mut client).expect("Failed to read line");
+
+            let client = match client.trim()
+            {
+                ""     => None,
+                client => match db::projects::find_client(db, client)
+                {
+                    Ok(id)   => Some(id),
+                    Err(err) => { eprintln!("{}", err); continue; }
+                },
+            };
+
+            match db::projects::add_project(db, &name, client)
+            {
+                Ok(_)    => println!("Project added"),
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+        else if opt == "a"
+        {
+            let id = match print_all_acts_get_choice(db)
+            {
+                Ok(value) => value,
+                Err(err)  => { eprintln!("{}", err); continue; }
+            };
+
+            let projects = db::projects::get_projects(db).unwrap_or_default();
+            println!("ID\tProject");
+            for project in projects.iter()
+            {
+                println!("{}\t{}", project.id, project.name);
+            }
+            print!("Project ID (empty for none): ");
+            io::stdout().flush().unwrap();
+            let mut project = String::new();
+            io::stdin().read_line(&mut project).expect("Failed to read line");
+
+            let project = match project.trim()
+            {
+                ""      => None,
+                project => match db::projects::find_project(db, project)
+                {
+                    Ok(id)   => Some(id),
+                    Err(err) => { eprintln!("{}", err); continue; }
+                },
+            };
+
+            match db::projects::assign(db, id, project)
+            {
+                Ok(())   => println!("Activity assigned"),
+                Err(err) => eprintln!("{}", err),
+            }
         }
         else if opt == "q"
         {
@@ -461,11 +913,22 @@ pub fn manual(db : &mut Connection)
         minutes_int = minutes.trim().parse().unwrap_or(-1);
     }
 
+    let tags = read_tags();
+    let note = read_note();
+
     println!("---------------------------------------------------------------");
     println!("Confirm your entry!");
     println!("  Duration: {} hours and {} minutes", hours_int, minutes_int);
     println!("  Day&Date: {}, {}", dtbeg.weekday(), dtbeg.format("%Y-%m-%d"));
     println!("  Activity: {}", db::get_activityname_for_id(db, idint).unwrap());
+    if !tags.is_empty()
+    {
+        println!("  Tags    : {}", tags.join(", "));
+    }
+    if let Some(note) = &note
+    {
+        println!("  Note    : {}", note);
+    }
     println!("---------------------------------------------------------------");
     print!("Is above information correct? (y/n): ");
     io::stdout().flush().unwrap();
@@ -490,7 +953,14 @@ pub fn manual(db : &mut Connection)
     let dtend = dtbeg + 
         Duration::hours(hours_int) + Duration::minutes(minutes_int);
 
-    db::enter_into_db(db, &dtbeg, &dtend, idint)?;
+    // all or nothing: the hours w/ their tags and note
+    let tx = db.transaction()?;
+    let sid = db::sessions::enter(&tx, &dtbeg, &dtend, idint,
+                                  db::sessions::Kind::Work,
+                                  db::sessions::Source::Manual)?;
+    db::tags::add(&tx, sid, &tags)?;
+    db::sessions::set_note(&tx, sid, note.as_deref())?;
+    tx.commit()?;
 
     println!("Your entry has successfully been added");
     println!();
@@ -513,12 +983,15 @@ pub fn delete(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
         let naivedate = NaiveDate::parse_from_str(
             entry.date.as_str(), "%Y-%m-%d")?;
         let weekday = naivedate.weekday();
+        let notes = db::sessions::notes_of_day(db, entry.id, naivedate)?;
 
-        println!("#{}\tDate: {} {}, hours: {:5.2}, Activity: {}",
+        println!("#{}\tDate: {} {}, hours: {:5.2}, Activity: {}{}",
                  index,
                  weekday,
                  entry.date, entry.hours,
                  db::get_activityname_for_id(db, entry.id).unwrap(),
+                 if notes.is_empty() { String::new() }
+                 else { format!(", Note: {}", notes.join("; ")) },
                  );
 
         validindexes.push(index as i32);
@@ -607,6 +1080,223 @@ pub fn delete(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
     Ok(())
 }
 
+/// edit entries of any date range: change an entry's hours, move hours to
+/// another activity or split one of its sessions (optionally assigning the
+/// second part to another activity); history, total hours and sessions are
+/// kept consistent by the db functions used
+pub fn edit(db : &mut Connection) -> Result<(), Box<dyn error::Error>>
+{
+    let today = Local::now().date_naive();
+
+    println!();
+    println!("Search entries to edit (YYYY-MM-DD, today or yesterday)");
+
+    let from = read_date("  From: ", today);
+    let to   = read_date("  To  : ", today);
+
+    let actid = if read_yes_no("Only entries of one activity? (y/n): ")
+    {
+        match print_all_acts_get_choice(db)
+        {
+            Ok(value) => Some(value),
+            Err(err)  => { eprintln!("{}", err); return Ok(()); }
+        }
+    }
+    else
+    {
+        None
+    };
+
+    let historyvec = db::retrieve_history(db, from, to, actid)?;
+
+    if historyvec.is_empty()
+    {
+        println!("No entries found");
+        return Ok(());
+    }
+
+    println!("---------------------------------------------------------------");
+
+    for (index, entry) in historyvec.iter().enumerate()
+    {
+        let naivedate = NaiveDate::parse_from_str(
+            entry.date.as_str(), "%Y-%m-%d")?;
+        let notes = db::sessions::notes_of_day(db, entry.id, naivedate)?;
+
+        println!("#{}\tDate: {} {}, hours: {:5.2}, Activity: {}{}",
+                 index,
+                 naivedate.weekday(),
+                 entry.date, entry.hours,
+                 db::get_activityname_for_id(db, entry.id)?,
+                 if notes.is_empty() { String::new() }
+                 else { format!(", Note: {}", notes.join("; ")) },
+                 );
+    }
+    println!("---------------------------------------------------------------");
+
+    println!();
+    println!("Specify a valid entry number");
+    println!("  'q' to go back to main");
+
+    let index = loop
+    {
+        let input = read_input("Your input: #");
+        if input == "q" { return Ok(()); }
+
+        if let Ok(index) = input.parse::<usize>()
+        {
+            if index < historyvec.len() { break index; }
+        }
+    };
+
+    let entry = &historyvec[index];
+    let date  = NaiveDate::parse_from_str(entry.date.as_str(), "%Y-%m-%d")?;
+
+    println!();
+    println!("What do you want to do with this entry?");
+    println!("  1) Change its hours");
+    println!("  2) Move hours to another activity");
+    println!("  3) Split one of its sessions");
+    println!("  ('q' to go back to main)");
+
+    let opt = loop
+    {
+        let input = read_input("Your input: ");
+        if ["1", "2", "3", "q"].contains(&input.as_str()) { break input; }
+    };
+
+    let result = match opt.as_str()
+    {
+        "1" => {
+            let hours = read_hours("New duration (HH:MM or decimal hours): ");
+            db::set_day_hours(db, entry.id, date, hours)
+        },
+        "2" => {
+            println!("To which activity should the hours be moved?");
+            let into = match print_all_acts_get_choice(db)
+            {
+                Ok(value) => value,
+                Err(err)  => { eprintln!("{}", err); return Ok(()); }
+            };
+            let hours = read_hours("Hours to move (HH:MM or decimal hours): ");
+            db::move_hours(db, entry.id, into, date, hours)
+        },
+        "3" => edit_split(db, entry.id, date),
+        _ => return Ok(()),
+    };
+
+    match result
+    {
+        Ok(()) => println!("Entry updated"),
+        Err(err) => eprintln!("Entry not updated: {}", err),
+    }
+
+    Ok(())
+}
+
+/// split one of an activity's sessions on a date; part of edit()
+fn edit_split(db : &mut Connection, actid : i32, date : NaiveDate)
+    -> Result<(), Box<dyn error::Error>>
+{
+    let sessions : Vec<db::sessions::SessionRow> =
+        db::sessions::get(db, date, date)?
+        .into_iter()
+        .filter(|s| s.id == actid && s.kind == db::sessions::Kind::Work)
+        .collect();
+
+    if sessions.is_empty()
+    {
+        return Err("No sessions recorded for this entry".into());
+    }
+
+    println!("---------------------------------------------------------------");
+    println!("ID\tDate       Begin-End    Hours");
+    for session in &sessions
+    {
+        println!("{}\t{} {}-{} {:6.2}",
+                 session.sid,
+                 session.started.format("%Y-%m-%d"),
+                 session.started.format("%H:%M"),
+                 session.ended.format("%H:%M"),
+                 session.hours());
+    }
+    println!("---------------------------------------------------------------");
+
+    let session = loop
+    {
+        let input = read_input("Session to split (ID, 'q' to abort): ");
+        if input == "q" { return Err("Aborted".into()); }
+
+        if let Some(s) = sessions.iter().find(|s| input.parse() == Ok(s.sid))
+        {
+            break s;
+        }
+    };
+
+    let at = loop
+    {
+        let input = read_input("Split at (HH:MM or YYYY-MM-DD HH:MM): ");
+        let at = cli::parse_datetime(&input, session.started.date_naive());
+        if let Some(at) = at { break at; }
+    };
+
+    let second = db::sessions::split(db, session.sid, &at)?;
+
+    if read_yes_no("Assign the second part to another activity? (y/n): ")
+    {
+        let into = print_all_acts_get_choice(db)?;
+        db::sessions::reassign(db, second, into)?;
+    }
+
+    Ok(())
+}
+
+/// prompt and read a line of user input (trimmed)
+pub(crate) fn read_input(prompt : &str) -> String
+{
+    print!("{}", prompt);
+    io::stdout().flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("Failed to read line");
+
+    input.trim().to_string()
+}
+
+fn read_yes_no(prompt : &str) -> bool
+{
+    loop
+    {
+        match read_input(prompt).as_str()
+        {
+            "y" => return true,
+            "n" => return false,
+            _   => (),
+        }
+    }
+}
+
+fn read_date(prompt : &str, today : NaiveDate) -> NaiveDate
+{
+    loop
+    {
+        if let Some(date) = cli::parse_date(&read_input(prompt), today)
+        {
+            return date;
+        }
+    }
+}
+
+fn read_hours(prompt : &str) -> f64
+{
+    loop
+    {
+        if let Some(duration) = cli::parse_duration(&read_input(prompt))
+        {
+            return duration.num_seconds() as f64 / 3600.;
+        }
+    }
+}
+
 /// end of program routine
 pub fn quit()
 {
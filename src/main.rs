@@ -1,3 +1,4 @@
+use std::env;
 use std::error;
 use std::fs;
 use std::io;
@@ -23,6 +24,13 @@ fn main() -> Result<(), Box<dyn error::Error>>
     let dcpath_exists: bool;
     let dbpath_exists: bool;
 
+    // any arguments: run a single non-interactive command (see cli module)
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty()
+    {
+        return run_command(projdir, &args);
+    }
+
     println!();
 
     if let Some(d) = projdir
@@ -53,7 +61,7 @@ fn main() -> Result<(), Box<dyn error::Error>>
     println!("Version : {}", VERSION);
     println!("Database used: {:?}", dbpath);
 
-    let mut db = Connection::open(dbpath)?; // create/open db
+    let mut db = Connection::open(&dbpath)?; // create/open db
     if !dbpath_exists
     {
         println!("initializing db w/ needed tables");
@@ -62,14 +70,16 @@ fn main() -> Result<(), Box<dyn error::Error>>
 
     /* BEGIN
      * db::check()
-     *      a) checks integrity, if tables not detected
-     *      b) calls db::init() for initilization
-     *      c) which calls crate::conf() so user sets up activities etc
+     *      a) migrates dbs of an older schema version (after a backup)
+     *      b) checks integrity, errors if expected tables/columns missing
      * whether db is new or not, all we do is
      */
-    timetracker::db::check(&db)?;
+    timetracker::db::check(&db, Some(&dbpath))?;
     /* END */
 
+    // a tracking session might have been left running (crash, closed term)
+    timetracker::recover(&mut db)?;
+
 
     loop
     {
@@ -82,6 +92,7 @@ fn main() -> Result<(), Box<dyn error::Error>>
         println!("  1) track");
         println!("  2) manual entry");
         println!("  3) delete entry");
+        println!("  8) edit entries");
         println!();
         println!("  4) stats");
         println!("  5) stats (yearly)");
@@ -106,7 +117,41 @@ fn main() -> Result<(), Box<dyn error::Error>>
             "5" => timetracker::statsyear(&mut db)?,
             "6" => timetracker::conf(&mut db)?,
             "7" => timetracker::quit(),
+            "8" => timetracker::edit(&mut db)?,
             _ => (),
         }
     }
 }
+
+/// non-interactive counterpart to main(); creates db if needed (without
+/// prompting for activities), checks it, and runs the given command
+fn run_command(projdir: Option<ProjectDirs>, args: &[String])
+    -> Result<(), Box<dyn error::Error>>
+{
+    let dcpath = match projdir
+    {
+        Some(d) => d.config_dir().to_path_buf(),
+        None => panic!("Could not retrieve OS specific configuration folder!"),
+    };
+    let dbpath = dcpath.join(DB_NAME);
+
+    if !dcpath.exists()
+    {
+        fs::create_dir_all(&dcpath)?;
+    }
+
+    let dbpath_exists = dbpath.exists();
+    let mut db = Connection::open(&dbpath)?;
+    if !dbpath_exists
+    {
+        db::create_tables(&db)?;
+    }
+
+    if let Some((from, backup)) = db::upgrade(&db, Some(&dbpath))?
+    {
+        eprintln!("Migrated db from schema version {} (backup: {:?})",
+                  from, backup);
+    }
+
+    timetracker::cli::run(&mut db, args)
+}
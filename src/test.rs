@@ -25,6 +25,9 @@ pub fn initialize_db(conn : &mut Connection) -> ()
     conn.execute(SQL_CREATE_HIS, ()).unwrap_or_else(|_| {
         panic!("Can't create table on in memory test db")
     });
+    crate::db::migrations::migrate(conn, None).unwrap_or_else(|_| {
+        panic!("Can't migrate in memory test db")
+    });
 }
 
 pub fn populate_db_w_activities(conn : &mut Connection) -> ()
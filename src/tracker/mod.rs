@@ -6,6 +6,8 @@ use std::sync::{Arc, atomic::AtomicBool, atomic::Ordering};
 use std::thread;
 use std::time::Duration;
 
+use crate::db::goals::Progress;
+
 /* A previous implementation returned the seconds (via a since removed custom
  * struct denoting a time duration, just a side note) from the workloop to be
  * used to denote time passed;
@@ -15,7 +17,14 @@ use std::time::Duration;
  * (not here, in lib.rs / db module) to calculate time passed
  */
 
-pub fn workloop() 
+/// width the timer line is cut to, so it doesn't wrap (and garble the
+/// redraws) in narrow terminals
+const LINE_WIDTH : usize = 60;
+
+/// runs the timer until Enter (returns false) or q-Enter (returns true);
+/// the progress towards `goal` is shown next to the timer, counting the
+/// running time in
+pub fn workloop(goal : Option<Progress>)
     -> Result<bool, Box<dyn error::Error>>
 {
     let shouldrun = Arc::new(AtomicBool::new(true));
@@ -45,7 +54,14 @@ pub fn workloop()
                 mm = seconds % 3600 / 60;
                 ss = seconds % 60;
 
-                print!("  {:02}:{:02}:{:02}\r", hh, mm, ss);
+                let progress = goal.as_ref()
+                    .map(|goal| goal.short(seconds as f64 / 3600.))
+                    .unwrap_or_default();
+                let line : String = format!("  {:02}:{:02}:{:02}  {}",
+                                            hh, mm, ss, progress)
+                    .chars().take(LINE_WIDTH).collect();
+
+                print!("{}\r", line);
                 io::stdout().flush().unwrap();
                 count = 0;
             }